@@ -0,0 +1,181 @@
+use std::io;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    harpoon::HarpoonEvent,
+    ipc::{self, Query, Request},
+    window::{ApplicationWindow, WindowStatus},
+};
+
+pub const USAGE: &str =
+    "Usage: harpoon-rs [list | add | goto <n> | next | prev | remove <n> | menu | quit] [--json]";
+
+/// A command for a running instance of harpoon. Slots are numbered from 1, the way the
+/// quick menu shows them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Print the entries of the active list
+    List,
+    /// Add the focused window to the active list
+    Add,
+    Goto(usize),
+    Next,
+    Prev,
+    Remove(usize),
+    /// Open or close the quick menu
+    Menu,
+    Quit,
+}
+
+impl Command {
+    fn request(&self) -> Request {
+        let event = match self {
+            Command::List => {
+                return Request::Query {
+                    query: Query::ListEntries,
+                }
+            }
+            Command::Add => HarpoonEvent::AddCurrentApplicationWindow,
+            Command::Goto(slot) => HarpoonEvent::NavigateToWindowByIndex(slot - 1),
+            Command::Next => HarpoonEvent::NavigateToNextWindow,
+            Command::Prev => HarpoonEvent::NavigateToPreviousWindow,
+            Command::Remove(slot) => HarpoonEvent::DeleteWindow(slot - 1),
+            Command::Menu => HarpoonEvent::ToggleQuickMenu,
+            Command::Quit => HarpoonEvent::Quit,
+        };
+        Request::Event(event)
+    }
+}
+
+/// Parses the command line arguments, without the program name. Returns `None` when there
+/// is no command, which starts harpoon itself, and whether the output should be JSON.
+pub fn parse(args: &[String]) -> Result<Option<(Command, bool)>> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--json")
+        .collect();
+
+    let slot = |arg: Option<&&str>| -> Result<usize> {
+        let arg = arg.ok_or_else(|| anyhow!("missing slot number"))?;
+        match arg.parse::<usize>() {
+            Ok(slot) if slot > 0 => Ok(slot),
+            _ => Err(anyhow!("invalid slot number {:?}, slots start at 1", arg)),
+        }
+    };
+
+    let command = match args.first() {
+        None if json => return Err(anyhow!("--json needs a command")),
+        None => return Ok(None),
+        Some(&"list") => Command::List,
+        Some(&"add") => Command::Add,
+        Some(&"goto") => Command::Goto(slot(args.get(1))?),
+        Some(&"next") => Command::Next,
+        Some(&"prev") => Command::Prev,
+        Some(&"remove") => Command::Remove(slot(args.get(1))?),
+        Some(&"menu") => Command::Menu,
+        Some(&"quit") => Command::Quit,
+        Some(command) => return Err(anyhow!("unknown command {:?}", command)),
+    };
+    Ok(Some((command, json)))
+}
+
+/// Sends the command to the running instance and prints its reply. Returns whether the
+/// command succeeded.
+pub fn run(command: &Command, json: bool) -> bool {
+    let reply = match ipc::request(&command.request()) {
+        Ok(reply) => reply,
+        Err(err) => {
+            let message = match err.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+                    "harpoon-rs is not running".to_string()
+                }
+                _ => format!("Error talking to harpoon-rs: {}", err),
+            };
+            if json {
+                println!("{}", serde_json::json!({ "error": message }));
+            } else {
+                println!("{}", message);
+            }
+            return false;
+        }
+    };
+
+    let error = reply.get("error").and_then(Value::as_str);
+    if json {
+        println!("{}", reply);
+    } else if let Some(error) = error {
+        println!("Error: {}", error);
+    } else if *command == Command::List {
+        print_entries(&reply);
+    }
+    error.is_none()
+}
+
+fn print_entries(reply: &Value) {
+    let list = reply
+        .get("list")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let entries: Vec<Option<ApplicationWindow>> = reply
+        .get("entries")
+        .and_then(|entries| serde_json::from_value(entries.clone()).ok())
+        .unwrap_or_default();
+
+    println!("{}:", list);
+    if entries.is_empty() {
+        println!("  no windows");
+    }
+    for (index, entry) in entries.iter().enumerate() {
+        let window = match entry {
+            Some(window) => window,
+            None => {
+                println!("  {}: empty", index + 1);
+                continue;
+            }
+        };
+        let name = window.label.as_ref().unwrap_or(&window.title);
+        match window.status {
+            WindowStatus::Alive => println!("  {}: {} ({})", index + 1, name, window.process_name),
+            status => println!(
+                "  {}: {} ({}, {:?})",
+                index + 1,
+                name,
+                window.process_name,
+                status
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Command};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(&args(&[])).unwrap(), None);
+        assert_eq!(
+            parse(&args(&["goto", "3", "--json"])).unwrap(),
+            Some((Command::Goto(3), true))
+        );
+        assert_eq!(
+            parse(&args(&["--json", "list"])).unwrap(),
+            Some((Command::List, true))
+        );
+        assert_eq!(
+            parse(&args(&["remove", "1"])).unwrap(),
+            Some((Command::Remove(1), false))
+        );
+        assert!(parse(&args(&["goto", "0"])).is_err());
+        assert!(parse(&args(&["remove"])).is_err());
+        assert!(parse(&args(&["launch"])).is_err());
+    }
+}
@@ -1,14 +1,142 @@
 use fltk::enums::{Key, Shortcut};
 use mki::Keyboard;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::{harpoon::HarpoonEvent, keyboard::FltkKeyCombination, quick_menu::QuickMenuEvent};
+use crate::{
+    harpoon::HarpoonEvent,
+    keyboard::FltkKeyCombination,
+    quick_menu::QuickMenuEvent,
+    window::{ApplicationWindow, RestorePolicy},
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Config {
     pub leader: Vec<Keyboard>,
     pub actions: Vec<Action<HarpoonEvent>>,
     pub quick_menu_config: StoredQuickMenuConfig,
+    /// Launch the program of a closed window when it is selected, and bind the entry to
+    /// the new window.
+    #[serde(default)]
+    pub relaunch_closed_windows: bool,
+    /// Arguments and working directories to use when relaunching programs
+    #[serde(default)]
+    pub launch_options: Vec<LaunchOptions>,
+    /// How much of a window's saved layout to restore when navigating to it
+    #[serde(default)]
+    pub restore_policy: RestorePolicy,
+    /// Restore policies for specific programs, these take precedence over `restore_policy`
+    #[serde(default)]
+    pub restore_policies: Vec<RestorePolicyRule>,
+    /// The monitor to move windows to when the monitor they were on is disconnected, the
+    /// primary monitor is used when this isn't set
+    #[serde(default)]
+    pub fallback_monitor: Option<String>,
+    /// A JSONL file every event is appended to, so the session can be replayed with
+    /// `--replay`. Nothing is recorded when this isn't set.
+    #[serde(default)]
+    pub record_events: Option<String>,
+    /// Entries that focus a matching window, or launch a program when there is none. They
+    /// are bound to keys with `RunOrRaise` actions
+    #[serde(default)]
+    pub run_or_raise: Vec<RunOrRaiseRule>,
+    /// How often to refresh the titles and status of the entries in all lists, in
+    /// milliseconds. They are also refreshed when the quick menu opens. With 0 they are only
+    /// refreshed then
+    #[serde(default = "default_refresh_interval_ms")]
+    pub refresh_interval_ms: u64,
+}
+
+fn default_refresh_interval_ms() -> u64 {
+    2000
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RestorePolicyRule {
+    /// The program this policy applies to
+    pub process_path: String,
+    pub policy: RestorePolicy,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LaunchOptions {
+    /// The program these options apply to
+    pub process_path: String,
+    #[serde(default)]
+    pub arguments: Vec<String>,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RunOrRaiseRule {
+    /// The name `RunOrRaise` actions refer to the rule by
+    pub name: String,
+    #[serde(default)]
+    pub process_path: Option<String>,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    /// A regular expression the title of the window has to match
+    #[serde(default)]
+    pub title: Option<TitlePattern>,
+    /// The program to launch when no window matches
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Vec<String>,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+}
+
+impl RunOrRaiseRule {
+    /// Returns whether the window matches everything the rule asks for. A rule that asks for
+    /// nothing matches no window.
+    pub fn matches(&self, window: &ApplicationWindow) -> bool {
+        if self.process_path.is_none() && self.process_name.is_none() && self.title.is_none() {
+            return false;
+        }
+        if let Some(process_path) = &self.process_path {
+            if *process_path != window.process_path {
+                return false;
+            }
+        }
+        if let Some(process_name) = &self.process_name {
+            if *process_name != window.process_name {
+                return false;
+            }
+        }
+        if let Some(title) = &self.title {
+            return title.0.is_match(&window.title);
+        }
+        true
+    }
+}
+
+/// A regular expression for window titles. It is compiled when the config is loaded, so an
+/// invalid pattern makes loading the config fail instead of never matching.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct TitlePattern(Regex);
+
+impl TryFrom<String> for TitlePattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern)
+            .map(TitlePattern)
+            .map_err(|err| format!("invalid title pattern {:?}: {}", pattern, err))
+    }
+}
+
+impl From<TitlePattern> for String {
+    fn from(pattern: TitlePattern) -> String {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl PartialEq for TitlePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -123,14 +251,50 @@ impl Config {
                     keys: vec![Keyboard::N],
                     action: HarpoonEvent::NavigateToPreviousWindow,
                 },
+                Action {
+                    keys: vec![Keyboard::Comma],
+                    action: HarpoonEvent::NavigateBack,
+                },
+                Action {
+                    keys: vec![Keyboard::Period],
+                    action: HarpoonEvent::NavigateForward,
+                },
+                Action {
+                    keys: vec![Keyboard::Number6],
+                    action: HarpoonEvent::ToggleAlternateWindow,
+                },
                 Action {
                     keys: vec![Keyboard::A],
                     action: HarpoonEvent::AddCurrentApplicationWindow,
                 },
+                Action {
+                    keys: vec![Keyboard::D],
+                    action: HarpoonEvent::RemoveCurrentApplicationWindow,
+                },
+                Action {
+                    keys: vec![Keyboard::G],
+                    action: HarpoonEvent::RecaptureGeometry,
+                },
                 Action {
                     keys: vec![Keyboard::S],
                     action: HarpoonEvent::ToggleInhibit,
                 },
+                Action {
+                    keys: vec![Keyboard::RightBrace],
+                    action: HarpoonEvent::SwitchToNextList,
+                },
+                Action {
+                    keys: vec![Keyboard::LeftBrace],
+                    action: HarpoonEvent::SwitchToPreviousList,
+                },
+                Action {
+                    keys: vec![Keyboard::W],
+                    action: HarpoonEvent::CycleApplicationWindows,
+                },
+                Action {
+                    keys: vec![Keyboard::B],
+                    action: HarpoonEvent::RebindToCurrentWindow,
+                },
             ],
             quick_menu_config: StoredQuickMenuConfig {
                 actions: vec![
@@ -190,6 +354,18 @@ impl Config {
                         keys: vec![Keyboard::LeftShift, Keyboard::D],
                         action: QuickMenuEvent::Cut,
                     },
+                    Action {
+                        keys: vec![Keyboard::Y],
+                        action: QuickMenuEvent::Yank,
+                    },
+                    Action {
+                        keys: vec![Keyboard::X],
+                        action: QuickMenuEvent::Delete,
+                    },
+                    Action {
+                        keys: vec![Keyboard::Delete],
+                        action: QuickMenuEvent::Delete,
+                    },
                     Action {
                         keys: vec![Keyboard::P],
                         action: QuickMenuEvent::PasteDown,
@@ -198,9 +374,74 @@ impl Config {
                         keys: vec![Keyboard::LeftShift, Keyboard::P],
                         action: QuickMenuEvent::PasteUp,
                     },
+                    Action {
+                        keys: vec![Keyboard::R],
+                        action: QuickMenuEvent::CycleRestorePolicy,
+                    },
+                    Action {
+                        keys: vec![Keyboard::G],
+                        action: QuickMenuEvent::RecaptureGeometry,
+                    },
+                    Action {
+                        keys: vec![Keyboard::U],
+                        action: QuickMenuEvent::Undo,
+                    },
+                    Action {
+                        keys: vec![Keyboard::LeftControl, Keyboard::R],
+                        action: QuickMenuEvent::Redo,
+                    },
+                    Action {
+                        keys: vec![Keyboard::A],
+                        action: QuickMenuEvent::AddToGroup,
+                    },
+                    Action {
+                        keys: vec![Keyboard::N],
+                        action: QuickMenuEvent::Rename,
+                    },
+                    Action {
+                        keys: vec![Keyboard::F2],
+                        action: QuickMenuEvent::Rename,
+                    },
+                    Action {
+                        keys: vec![Keyboard::H],
+                        action: QuickMenuEvent::AssignHotkey,
+                    },
                 ],
             },
+            relaunch_closed_windows: false,
+            launch_options: vec![],
+            restore_policy: RestorePolicy::Geometry,
+            restore_policies: vec![],
+            fallback_monitor: None,
+            record_events: None,
+            run_or_raise: vec![],
+            refresh_interval_ms: default_refresh_interval_ms(),
+        }
+    }
+
+    /// Returns the restore policy to use for a window, the entry's own policy wins over the
+    /// policy configured for its program, which wins over the default policy.
+    pub fn get_restore_policy(&self, window: &ApplicationWindow) -> RestorePolicy {
+        if let Some(policy) = window.restore_policy {
+            return policy;
         }
+        self.restore_policies
+            .iter()
+            .find(|rule| rule.process_path == window.process_path)
+            .map(|rule| rule.policy)
+            .unwrap_or(self.restore_policy)
+    }
+
+    /// Returns the launch options for the program at the given path, if any are configured.
+    pub fn get_launch_options(&self, process_path: &str) -> Option<&LaunchOptions> {
+        self.launch_options
+            .iter()
+            .find(|options| options.process_path == process_path)
+    }
+
+    /// Returns the run-or-raise rule with the given name.
+    pub fn get_run_or_raise_rule(&self, name: &str) -> Option<&RunOrRaiseRule> {
+        self.run_or_raise.iter().find(|rule| rule.name == name)
     }
 
     pub fn get_action_shortcut_string(&self, event: &HarpoonEvent) -> Option<String> {
@@ -257,3 +498,35 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RunOrRaiseRule;
+    use crate::window::mock::MockBackend;
+
+    #[test]
+    fn run_or_raise_rule_matches_every_criterion() {
+        let backend = MockBackend::new();
+        let docs = backend.open_window(1, "/usr/bin/firefox", "Rust docs - Mozilla Firefox");
+        let mail = backend.open_window(2, "/usr/bin/firefox", "Inbox - Mozilla Firefox");
+        let mut rule = RunOrRaiseRule {
+            name: "docs".to_string(),
+            process_path: None,
+            process_name: Some("firefox".to_string()),
+            title: Some("^Rust docs".to_string().try_into().unwrap()),
+            command: "firefox".to_string(),
+            arguments: vec![],
+            working_directory: None,
+        };
+        assert!(rule.matches(&docs));
+        assert!(!rule.matches(&mail));
+
+        rule.process_name = None;
+        rule.title = None;
+        assert!(!rule.matches(&docs));
+
+        let invalid = r#"{"name": "docs", "title": "(Rust docs", "command": "firefox"}"#;
+        let err = serde_json::from_str::<RunOrRaiseRule>(invalid).unwrap_err();
+        assert!(err.to_string().contains("invalid title pattern"));
+    }
+}
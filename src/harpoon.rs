@@ -1,42 +1,67 @@
-use std::sync::{
-    mpsc::{self, Receiver, Sender, TryRecvError},
-    Arc, Mutex,
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use crate::{
     config,
+    ipc::{self, Replies, Snapshot},
+    keyboard,
+    launcher::PendingLaunch,
+    layout::{self, Layout},
+    list::{HarpoonList, DEFAULT_LIST_NAME},
+    monitor,
     quick_menu::{QuickMenu, QuickMenuStateUpdate},
-    window::{create_window, get_current_window, get_window_title, navigate_to_window},
+    recorder::Recorder,
+    reducer::{self, Effect, State},
+    state,
+    window::{self, RestorePolicy, WindowBackend, WindowStatus},
 };
 use crate::{quick_menu::QuickMenuEvent, window::ApplicationWindow};
 use anyhow::Result;
-use fltk::{
-    app::{self},
-    prelude::*,
-};
+use fltk::app::{self};
 use mki::Keyboard;
 
 use serde::{Deserialize, Serialize};
-use windows::{
-    Win32::{
-        Foundation::HWND,
-        UI::WindowsAndMessaging::{GetForegroundWindow, IsWindow},
-    },
-};
 
-pub struct Harpoon {
-    quick_menu: QuickMenu,
+pub struct Harpoon<B: WindowBackend> {
+    backend: Arc<B>,
+    quick_menu: QuickMenu<B>,
     pub event_receiver: Receiver<HarpoonEvent>,
     pub event_sender: Arc<Mutex<Sender<HarpoonEvent>>>,
     config: config::Config,
     /// whether or not to disable keyboard events from being inhibited to other applications
     disable_inhibit: bool,
-    windows: Vec<ApplicationWindow>,
-    /// the last window id that was focused
-    last_window_id: Option<isize>,
-    clipboard: Option<ApplicationWindow>,
+    /// the window lists, only changed by `reducer::reduce` and by keeping entries in sync
+    /// with their windows
+    state: State,
+    /// named snapshots of window positions that can be applied to the active list
+    layouts: Vec<Layout>,
+    /// programs that were relaunched for closed windows and haven't opened a window yet
+    pending_launches: Vec<PendingLaunch>,
+    last_launch_poll: Instant,
+    /// when the titles and status of the entries were last refreshed
+    last_refresh: Instant,
+    /// appends every event to the configured recording, so the session can be replayed
+    recorder: Option<Recorder>,
+    /// the clients of the control socket that wait for their events to be handled
+    ipc_replies: Replies,
+    /// what the control socket answers queries with
+    ipc_snapshot: Arc<Mutex<Snapshot>>,
+    /// why the event that is being handled did nothing, if it did
+    refusal: Option<String>,
+    /// set when a `Quit` event was handled, stops the main loop
+    quit: bool,
+    /// the hotkeys of entries that are registered with mki
+    entry_hotkeys: Vec<Vec<Keyboard>>,
 }
 
+/// How often to look for the windows of relaunched programs.
+const LAUNCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum HarpoonEvent {
     AddCurrentApplicationWindow,
@@ -45,16 +70,98 @@ pub enum HarpoonEvent {
     NavigateToNextWindow,
     NavigateToPreviousWindow,
     NavigateToWindowByIndex(usize),
+    /// Navigate to the entry with the given hotkey, sent when the hotkey of an entry is pressed
+    NavigateToWindowByHotkey(Vec<Keyboard>),
+    /// Pin the focused window to the slot with the given index, leaving the other slots alone
+    AddCurrentWindowToSlot(usize),
+    /// Pin the focused window to the slot with the given index, replacing the window in it
+    ReplaceSlotWithCurrentWindow(usize),
+    /// Remove the focused window from the active list, if it is harpooned
+    RemoveCurrentApplicationWindow,
+    /// Remove the window from the slot with the given index, the slot stays empty
+    ClearSlot(usize),
+    /// Add the focused window to the group of the entry with the given index, so it is
+    /// raised together with the entry's window
+    AddCurrentWindowToGroup(usize),
+    /// Focus the next window of the focused window's program, if the program is harpooned
+    CycleApplicationWindows,
+    /// Bind the entry of the focused window's program to the focused window
+    RebindToCurrentWindow,
+    /// Navigate to the previously focused window in the focus history
+    NavigateBack,
+    /// Navigate to the next window in the focus history, after navigating back
+    NavigateForward,
+    /// Navigate to the window that was focused before the current one
+    ToggleAlternateWindow,
+    /// Focus the topmost window that matches the run-or-raise rule with the given name, or
+    /// launch the rule's command if no window matches
+    RunOrRaise(String),
     ToggleInhibit,
     Quit,
-    SwapWindows { from: usize, to: usize },
-    CutWindow(usize),
-    PasteWindow(usize),
+    SwapWindows {
+        from: usize,
+        to: usize,
+    },
+    /// Remove the window at the given index and put it in the register, or in the clipboard
+    /// of the list if there is no register
+    CutWindow {
+        index: usize,
+        register: Option<char>,
+    },
+    /// Copy the window at the given index to the register, or to the clipboard of the list
+    YankWindow {
+        index: usize,
+        register: Option<char>,
+    },
+    /// Remove the window at the given index, leaving the clipboard and registers alone
+    DeleteWindow(usize),
+    /// Insert the windows in the register, or in the clipboard of the list, at the given index
+    PasteWindow {
+        index: usize,
+        register: Option<char>,
+    },
+    /// Cycle the restore policy of the window at the given index
+    CycleRestorePolicy(usize),
+    /// Show the label instead of the title of the window at the given index, `None` or an
+    /// empty label shows the title again
+    RenameWindow {
+        index: usize,
+        label: Option<String>,
+    },
+    /// Give the entry at the given index a global hotkey that navigates to it, `None` takes
+    /// the hotkey away
+    AssignHotkey {
+        index: usize,
+        hotkey: Option<Vec<Keyboard>>,
+    },
+    /// Save the current position, size and state of the focused window, if it is harpooned
+    RecaptureGeometry,
+    /// Save the current position, size and state of the window at the given index
+    RecaptureGeometryByIndex(usize),
+    /// Make the list with the given name the active list
+    SwitchToList(String),
+    SwitchToNextList,
+    SwitchToPreviousList,
+    /// Create a list with the given name and make it the active list
+    CreateList(String),
+    /// Delete the list with the given name, the last remaining list can't be deleted
+    DeleteList(String),
+    /// Save the positions of the windows in the active list as a layout with the given name,
+    /// replacing an existing layout with that name
+    SaveLayout(String),
+    /// Move all windows of the active list to their positions in the layout with the given name
+    ApplyLayout(String),
+    DeleteLayout(String),
+    /// Revert the most recent edit to a list
+    Undo,
+    /// Make the most recently undone edit again
+    Redo,
     QuickMenuEvent(QuickMenuEvent),
 }
 
-impl Harpoon {
-    pub fn new() -> Harpoon {
+impl<B: WindowBackend> Harpoon<B> {
+    pub fn new(backend: B) -> Harpoon<B> {
+        let backend = Arc::new(backend);
         let (event_sender, event_receiver) = mpsc::channel::<HarpoonEvent>();
         let event_sender = Arc::new(Mutex::new(event_sender));
 
@@ -65,20 +172,68 @@ impl Harpoon {
                 config::Config::default()
             }
         };
-        let quick_menu = QuickMenu::new(Arc::clone(&event_sender), config.clone());
+        let quick_menu = QuickMenu::new(
+            Arc::clone(&backend),
+            Arc::clone(&event_sender),
+            config.clone(),
+        );
+
+        let (lists, active_list) = match state::load_state_from_disk() {
+            Ok(persisted) => persisted.into_lists(&backend.enumerate_windows()),
+            Err(e) => {
+                println!("Error loading state: {}", e);
+                (vec![HarpoonList::new(DEFAULT_LIST_NAME)], 0)
+            }
+        };
+
+        let layouts = match layout::load_layouts_from_disk() {
+            Ok(layouts) => layouts,
+            Err(e) => {
+                println!("Error loading layouts: {}", e);
+                vec![]
+            }
+        };
+
+        let state = State::new(lists, active_list);
+        let recorder = config.record_events.as_ref().and_then(|path| {
+            Recorder::open(path, state.to_persisted())
+                .map_err(|err| println!("Error opening recording {}: {}", path, err))
+                .ok()
+        });
+
+        let ipc_replies = Replies::default();
+        let ipc_snapshot = Arc::new(Mutex::new(Snapshot::default()));
+        let server = ipc::Server::new(
+            Arc::clone(&event_sender),
+            ipc_replies.clone(),
+            Arc::clone(&ipc_snapshot),
+        );
+        if let Err(err) = ipc::listen(server) {
+            println!("Error starting the control socket: {}", err);
+        }
 
         let mut harpoon = Harpoon {
+            backend,
             quick_menu,
             event_receiver,
             event_sender,
             config,
             disable_inhibit: false,
-            windows: vec![],
-            last_window_id: None,
-            clipboard: None,
+            state,
+            layouts,
+            pending_launches: vec![],
+            last_launch_poll: Instant::now(),
+            last_refresh: Instant::now(),
+            recorder,
+            ipc_replies,
+            ipc_snapshot,
+            refusal: None,
+            quit: false,
+            entry_hotkeys: vec![],
         };
 
-        let _app_hwnd = create_window();
+        harpoon.show_active_list();
+        harpoon.publish_snapshot();
 
         // let leader = harpoon.config.leader.clone();
         // let quick_menu_shortcut = harpoon
@@ -100,13 +255,16 @@ impl Harpoon {
         // ));
 
         harpoon.register_hooks();
+        harpoon.register_entry_hotkeys();
 
         harpoon
     }
 
     pub fn run(&mut self) {
-        loop {
+        while !self.quit {
             self.handle_main_events();
+            self.poll_pending_launches();
+            self.refresh_entries_periodically();
             // Somehow waiting for events also handles them in fltk-rs (??) so we don't need to
             // explicitly handle them here.
             match app::wait_for(1.0 / 120.0) {
@@ -114,48 +272,90 @@ impl Harpoon {
                 Err(err) => println!("Error waiting for fltk events: {}", err),
             };
         }
+        self.save_state();
+        ipc::stop_listening();
     }
 
     fn handle_main_events(&mut self) {
-        let msg = self.event_receiver.try_recv();
-        match msg {
-            Ok(event) => match event {
-                HarpoonEvent::ToggleQuickMenu => self.quick_menu.toggle(),
+        match self.event_receiver.try_recv() {
+            Ok(event) => self.handle_event(event),
+            Err(TryRecvError::Empty) => {
+                // No events to handle
+            }
+            Err(TryRecvError::Disconnected) => {
+                println!("Event channel disconnected");
+                app::quit();
+            }
+        }
+    }
 
-                HarpoonEvent::CloseQuickMenu
-                | HarpoonEvent::QuickMenuEvent(QuickMenuEvent::Quit) => self.quick_menu.hide(),
+    /// Handles an event, and if it was sent to the control socket, replies with the message
+    /// that was shown if the event did nothing.
+    fn handle_event(&mut self, event: HarpoonEvent) {
+        self.record_event(&event);
+        let reply = self.ipc_replies.take(&event);
+        self.refusal = None;
+        self.run_event(event);
+        if let Some(reply) = reply {
+            let result = match self.refusal.take() {
+                Some(message) => Err(message),
+                None => Ok(()),
+            };
+            // the client may have given up waiting
+            let _ = reply.send(result);
+        }
+    }
 
-                HarpoonEvent::QuickMenuEvent(event) => {
-                    self.quick_menu.handle_event(event);
+    fn run_event(&mut self, event: HarpoonEvent) {
+        match event {
+            HarpoonEvent::ToggleQuickMenu => {
+                // the menu shows the entries as they are now, not as of the last refresh
+                if !self.quick_menu.is_open() {
+                    self.refresh_entries();
                 }
+                self.quick_menu.toggle();
+            }
 
-                HarpoonEvent::AddCurrentApplicationWindow => {
-                    self.add_current_application_window().unwrap_or_else(|err| {
-                        println!("Error adding current application window: {}", err)
-                    });
-                }
+            HarpoonEvent::CloseQuickMenu | HarpoonEvent::QuickMenuEvent(QuickMenuEvent::Quit) => {
+                self.quick_menu.hide()
+            }
 
-                HarpoonEvent::NavigateToNextWindow => self.navigate_relative(1),
-                HarpoonEvent::NavigateToPreviousWindow => self.navigate_relative(-1),
-                HarpoonEvent::NavigateToWindowByIndex(i) => {
-                    self.navigate_to_window_by_index(i);
-                }
+            HarpoonEvent::QuickMenuEvent(event) => {
+                self.quick_menu.handle_event(event);
+            }
 
-                HarpoonEvent::SwapWindows { from, to } => self.swap_windows(from, to),
+            HarpoonEvent::Quit => self.quit = true,
+            HarpoonEvent::ToggleInhibit => self.toggle_disable_inhibit(),
 
-                HarpoonEvent::CutWindow(i) => self.cut_window(i),
-                HarpoonEvent::PasteWindow(i) => self.paste_window(i),
+            HarpoonEvent::NavigateBack => self.navigate_history(-1),
+            HarpoonEvent::NavigateForward => self.navigate_history(1),
+            HarpoonEvent::ToggleAlternateWindow => self.toggle_alternate_window(),
+            HarpoonEvent::RunOrRaise(name) => self.run_or_raise(&name),
+            HarpoonEvent::CycleApplicationWindows => self.cycle_application_windows(),
 
-                _ => {
-                    println!("Handling event {:?}", event);
+            HarpoonEvent::RecaptureGeometry => {
+                if let Some(window) = self.backend.current_window() {
+                    self.recapture_geometry(window);
                 }
-            },
-            Err(TryRecvError::Empty) => {
-                // No events to handle
             }
-            Err(TryRecvError::Disconnected) => {
-                println!("Event channel disconnected");
-                app::quit();
+            HarpoonEvent::RecaptureGeometryByIndex(i) => self.recapture_geometry_by_index(i),
+
+            HarpoonEvent::AssignHotkey {
+                hotkey: Some(ref hotkey),
+                ..
+            } if self.is_action_hotkey(hotkey) => self.refuse(&format!(
+                "{} is already used by an action in the config",
+                keyboard::hotkey_string(hotkey)
+            )),
+
+            _ if reducer::handles(&event) => self.dispatch(&event),
+
+            HarpoonEvent::SaveLayout(name) => self.save_layout(&name),
+            HarpoonEvent::ApplyLayout(name) => self.apply_layout(&name),
+            HarpoonEvent::DeleteLayout(name) => self.delete_layout(&name),
+
+            _ => {
+                println!("Handling event {:?}", event);
             }
         }
     }
@@ -186,141 +386,435 @@ impl Harpoon {
         );
     }
 
-    fn add_current_application_window(&mut self) -> Result<()> {
-        let windows = &mut self.windows;
-        let application_window = match get_current_window() {
-            Some(window) => window,
-            None => return Err(anyhow!("No window found")),
+    /// Registers the hotkeys of the entries in all lists, and unregisters those of entries
+    /// that were removed or given another hotkey since the last call.
+    fn register_entry_hotkeys(&mut self) {
+        let mut hotkeys: Vec<Vec<Keyboard>> = self
+            .state
+            .lists
+            .iter()
+            .flat_map(|list| list.windows())
+            .filter_map(|window| window.hotkey.clone())
+            .collect();
+        hotkeys.sort();
+        hotkeys.dedup();
+
+        for hotkey in self.entry_hotkeys.iter() {
+            if !hotkeys.contains(hotkey) {
+                mki::unregister_hotkey(hotkey);
+            }
+        }
+        for hotkey in hotkeys.iter() {
+            if !self.entry_hotkeys.contains(hotkey) {
+                let event = HarpoonEvent::NavigateToWindowByHotkey(hotkey.clone());
+                self.register_hotkey(hotkey, event, !self.disable_inhibit);
+            }
+        }
+        self.entry_hotkeys = hotkeys;
+    }
+
+    /// Returns whether the hotkey is taken by one of the configured actions. The keys are
+    /// compared as a set, since the config may list the modifiers in another order.
+    fn is_action_hotkey(&self, hotkey: &[Keyboard]) -> bool {
+        let mut hotkey = hotkey.to_vec();
+        hotkey.sort();
+        self.config.actions.iter().any(|action| {
+            let mut keys = self.config.leader.clone();
+            keys.extend(action.keys.iter().copied());
+            keys.sort();
+            keys == hotkey
+        })
+    }
+
+    /// Appends the event to the recording, together with the focused window.
+    fn record_event(&mut self, event: &HarpoonEvent) {
+        if self.recorder.is_none() {
+            return;
+        }
+        let focused = self.focused_window();
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.record(event, focused) {
+                println!("Error recording event: {}", err);
+            }
+        }
+    }
+
+    /// Returns the focused window. The quick menu has the focus while it is open, so events
+    /// from it act on the window that was focused before it opened.
+    fn focused_window(&self) -> Option<ApplicationWindow> {
+        let window = match self.quick_menu.is_open() {
+            true => self.quick_menu.focused_before_opening().cloned(),
+            false => self.backend.current_window(),
         };
+        window.map(|w| self.placed(w))
+    }
+
+    /// Runs an event that changes the lists through the reducer and carries out its effects.
+    fn dispatch(&mut self, event: &HarpoonEvent) {
+        self.state.focused = self.focused_window();
+        // remember where the cursor is, so it's restored when switching back to this list
+        self.state.lists[self.state.active_list].cursor = self.quick_menu.cursor();
+
+        let state = std::mem::replace(&mut self.state, State::new(vec![], 0));
+        let (state, effects) = reducer::reduce(state, event);
+        self.state = state;
 
-        if windows.contains(&application_window) {
-            return Ok(());
+        for effect in effects {
+            match effect {
+                Effect::Navigate(window) => {
+                    self.record_focused_window();
+                    self.navigate_to_window(*window);
+                }
+                Effect::ShowList(move_cursor) => {
+                    let list = self.state.active_list();
+                    self.quick_menu.update_state(
+                        QuickMenuStateUpdate::new()
+                            .with_windows(&list.slots)
+                            .with_list_name(&list.name)
+                            .with_move_cursor(move_cursor),
+                    );
+                }
+                Effect::ShowMessage(message) => self.show_message(&message),
+                Effect::Refuse(message) => self.refuse(&message),
+                Effect::SaveState => self.save_state(),
+            }
         }
+        self.register_entry_hotkeys();
+    }
 
-        if windows.len() == 0 {
-            windows.push(application_window);
-            self.quick_menu
-                .update_state(QuickMenuStateUpdate::new().with_windows(&self.windows));
-            return Ok(());
+    /// Navigate back (`delta` < 0) or forward (`delta` > 0) in the focus history, skipping
+    /// windows that aren't alive or aren't in the active list.
+    fn navigate_history(&mut self, delta: isize) {
+        self.record_focused_window();
+        let list = &self.state.lists[self.state.active_list];
+        let backend = self.backend.as_ref();
+        let is_available = |id: isize| backend.is_alive(id) && list.position(id).is_some();
+
+        let window_id = match delta < 0 {
+            true => self.state.history.back(is_available),
+            false => self.state.history.forward(is_available),
+        };
+        if let Some(window) = window_id.and_then(|id| self.find_window(id)) {
+            self.navigate_to_window(window);
         }
+    }
 
-        // if the window does aleady exist, update it
-        if let Some(index) = windows
-            .iter()
-            .position(|w| w.window_id == application_window.window_id)
+    /// Navigate to the window that was focused before the current one, like vim's `Ctrl-^`.
+    fn toggle_alternate_window(&mut self) {
+        self.record_focused_window();
+        if let Some(window) = self
+            .state
+            .history
+            .alternate()
+            .and_then(|id| self.find_window(id))
         {
-            let hwnd = windows[index].window_id;
-            windows[index] = application_window;
-            self.quick_menu.update_state(
-                QuickMenuStateUpdate::new()
-                    .with_windows(&self.windows)
-                    .with_active_window(hwnd),
-            );
-            return Ok(());
+            self.navigate_to_window(window);
+        }
+    }
+
+    /// Adds the focused window to the focus history if it is harpooned, so windows that were
+    /// focused without harpoon (e.g. with alt-tab) are remembered too.
+    fn record_focused_window(&mut self) {
+        let current_window_id = match self.backend.current_window() {
+            Some(window) => window.window_id,
+            None => return,
+        };
+        if self.find_window(current_window_id).is_some() {
+            self.state.history.visit(current_window_id);
+        }
+    }
+
+    /// Returns the entry of the active list with the given window id.
+    fn find_window(&self, window_id: isize) -> Option<ApplicationWindow> {
+        self.state.lists[self.state.active_list]
+            .windows()
+            .find(|w| w.window_id == window_id)
+            .cloned()
+    }
+
+    fn navigate_to_window(&mut self, window: ApplicationWindow) {
+        let exists = self.backend.is_alive(window.window_id);
+
+        if !exists {
+            let was_closed = window.status == WindowStatus::Closed;
+            let window = ApplicationWindow {
+                window_id: self.mark_closed(window.window_id),
+                status: WindowStatus::Closed,
+                ..window
+            };
+            if self.config.relaunch_closed_windows {
+                self.relaunch_window(&window);
+            }
+            if !was_closed {
+                let list = &self.state.lists[self.state.active_list];
+                self.quick_menu
+                    .update_state(QuickMenuStateUpdate::new().with_windows(&list.slots));
+                self.save_state();
+            }
+            return;
+        }
+
+        // raise the group members first, so the entry's own window ends up on top and focused
+        for member in window.group_members.iter() {
+            if self.backend.is_alive(member.window_id) {
+                let policy = self.config.get_restore_policy(member);
+                self.backend
+                    .navigate_to_window(&self.restored(member.clone()), policy);
+            }
         }
 
-        let hwnd = application_window.window_id;
-        windows.push(application_window);
+        let policy = self.config.get_restore_policy(&window);
+        self.backend
+            .navigate_to_window(&self.restored(window.clone()), policy);
+        self.state.history.visit(window.window_id);
+
+        let _ = self.update_window_title(window.window_id);
         self.quick_menu.update_state(
             QuickMenuStateUpdate::new()
-                .with_windows(&self.windows)
-                .with_active_window(hwnd),
+                .with_windows(&self.state.lists[self.state.active_list].slots)
+                .with_active_window(window.window_id),
         );
-
-        Ok(())
     }
 
-    fn navigate_to_window_by_index(&mut self, index: usize) {
-        let window = match self.windows.get(index) {
-            Some(window) => window,
-            None => return,
+    /// Focuses the topmost window that matches a run-or-raise rule, or launches the rule's
+    /// command and focuses its window once it appears.
+    fn run_or_raise(&mut self, name: &str) {
+        let rule = match self.config.get_run_or_raise_rule(name) {
+            Some(rule) => rule.clone(),
+            None => return self.refuse(&format!("There is no rule named {}", name)),
         };
-        self.navigate_to_window(window.clone());
-    }
-
-    /// get the current active window
-    ///
-    /// If the current window is in the list of windows, then we can
-    /// navigate relative to it.
-    /// Otherwise, navigate relative to the window last navigated to.
-    /// If all else fails, navigate to the first window in the list.
-    fn navigate_relative(&mut self, delta: isize) {
-        let hwnd = unsafe { GetForegroundWindow() }.0;
-
-        let windows = &self.windows;
-        let current_window_index = windows.iter().position(|w| w.window_id == hwnd);
-
-        if current_window_index.is_none() {
-            // navigate to the window to which the user navigated most recently
-            let hwnd = self.last_window_id;
-            if let Some(hwnd) = hwnd {
-                // find the window in the list of windows
-                match windows.iter().find(|w| w.window_id == hwnd) {
-                    Some(window) => {
-                        let window_clone = window.clone();
-                        self.navigate_to_window(window_clone);
-                        return;
-                    }
-                    None => {
-                        // if we can't find the last window, navigate to the first window
-                        self.navigate_to_window_by_index(1);
-                        return;
-                    }
-                };
+
+        let window = self
+            .backend
+            .enumerate_windows()
+            .into_iter()
+            .find(|w| rule.matches(w));
+        if let Some(window) = window {
+            self.record_focused_window();
+            // a harpooned window is restored like its entry, other windows are only focused
+            match self.find_window(window.window_id) {
+                Some(entry) => self.navigate_to_window(entry),
+                None => self
+                    .backend
+                    .navigate_to_window(&window, RestorePolicy::FocusOnly),
             }
-            self.navigate_to_window_by_index(1); // if there is no last window, navigate to the first window;
             return;
         }
 
-        let current_window_index = current_window_index.unwrap();
+        let already_pending = self
+            .pending_launches
+            .iter()
+            .any(|launch| launch.rule.as_ref() == Some(&rule));
+        if already_pending {
+            return;
+        }
+        match PendingLaunch::run(&rule, self.backend.as_ref()) {
+            Ok(launch) => self.pending_launches.push(launch),
+            Err(err) => println!("Error launching {}: {}", rule.command, err),
+        }
+    }
 
-        let windows_len = windows.len();
+    /// Focuses the next window of the focused window's program, so the other windows of a
+    /// harpooned browser or editor can be reached without harpooning each of them.
+    fn cycle_application_windows(&mut self) {
+        let focused = match self.focused_window() {
+            Some(window) => window,
+            None => return self.refuse("No window found"),
+        };
+        let harpooned = self
+            .state
+            .active_list()
+            .windows()
+            .any(|w| w.process_path == focused.process_path);
+        if !harpooned {
+            return self.refuse(&format!("{} isn't harpooned", focused.process_name));
+        }
 
-        let next_window_index =
-            (current_window_index as isize + windows_len as isize + delta) as usize % windows_len;
+        let mut windows = self
+            .backend
+            .enumerate_process_windows(&focused.process_path);
+        // the stacking order changes with every focus, the ids keep the order of the cycle
+        windows.sort_by_key(|w| w.window_id);
+        let next = match windows
+            .iter()
+            .position(|w| w.window_id == focused.window_id)
+        {
+            Some(index) => (index + 1) % windows.len(),
+            None => 0,
+        };
+        let window = match windows.into_iter().nth(next) {
+            Some(window) if window.window_id != focused.window_id => window,
+            _ => return self.refuse(&format!("{} has no other windows", focused.process_name)),
+        };
 
-        match windows.get(next_window_index) {
-            Some(window) => {
-                let window = window.clone();
-                self.navigate_to_window(window);
-            }
-            None => {
-                // if we can't find the last window, navigate to the first window
-                self.navigate_to_window_by_index(1);
-            }
+        self.record_focused_window();
+        // the entry's own window is restored like the entry, the others are only focused
+        match self.find_window(window.window_id) {
+            Some(entry) => self.navigate_to_window(entry),
+            None => self
+                .backend
+                .navigate_to_window(&window, RestorePolicy::FocusOnly),
         }
     }
 
-    fn navigate_to_window(&mut self, window: ApplicationWindow) {
-        let exists = unsafe { IsWindow(HWND(window.window_id)).as_bool() };
+    /// Launches the program of a closed window, the entry is rebound once the program opens
+    /// a new window.
+    fn relaunch_window(&mut self, window: &ApplicationWindow) {
+        let already_pending = self.pending_launches.iter().any(|launch| {
+            launch.window_id == window.window_id && launch.process_path == window.process_path
+        });
+        if already_pending {
+            return;
+        }
 
-        let closed_prefix = "[CLOSED] ";
+        let options = self.config.get_launch_options(&window.process_path);
+        match PendingLaunch::start(window, options, self.backend.as_ref()) {
+            Ok(launch) => self.pending_launches.push(launch),
+            Err(err) => println!("Error relaunching {}: {}", window.process_path, err),
+        }
+    }
 
-        if !exists {
-            if window.process_name.starts_with(closed_prefix) {
-                return;
+    /// Binds entries of relaunched programs to their new windows once they appear, and
+    /// focuses the windows of programs launched for run-or-raise rules.
+    fn poll_pending_launches(&mut self) {
+        if self.pending_launches.is_empty()
+            || self.last_launch_poll.elapsed() < LAUNCH_POLL_INTERVAL
+        {
+            return;
+        }
+        self.last_launch_poll = Instant::now();
+
+        let mut index = 0;
+        while index < self.pending_launches.len() {
+            let launch = &self.pending_launches[index];
+            if let Some(new_window) = launch.find_window(self.backend.as_ref()) {
+                let launch = self.pending_launches.remove(index);
+                match launch.rule {
+                    Some(_) => self
+                        .backend
+                        .navigate_to_window(&new_window, RestorePolicy::FocusOnly),
+                    None => self.rebind_window(&launch, new_window),
+                }
+                continue;
+            }
+            if launch.has_expired() {
+                println!("{} did not open a window in time", launch.process_path);
+                self.pending_launches.remove(index);
+                continue;
             }
-            let windows = &mut self.windows;
+            index += 1;
+        }
+    }
 
-            if let Some(index) = windows.iter().position(|w| w.window_id == window.window_id) {
-                windows[index].process_name = format!("{}{}", closed_prefix, window.process_name);
+    /// Points the entries of a closed window at the new window of its relaunched program,
+    /// then moves the new window to the saved position.
+    fn rebind_window(&mut self, launch: &PendingLaunch, new_window: ApplicationWindow) {
+        let mut rebound = None;
+        for list in self.state.lists.iter_mut() {
+            let entry = list
+                .windows_mut()
+                .find(|w| w.window_id == launch.window_id && w.process_path == launch.process_path);
+            if let Some(entry) = entry {
+                self.state
+                    .history
+                    .replace(entry.window_id, new_window.window_id);
+                self.state
+                    .edits
+                    .replace(entry.window_id, new_window.window_id);
+                self.state
+                    .registers
+                    .replace(entry.window_id, new_window.window_id);
+                entry.window_id = new_window.window_id;
+                entry.title = new_window.title.clone();
+                entry.status = WindowStatus::Rebound;
+                rebound = Some(entry.clone());
             }
+        }
+
+        if let Some(window) = rebound {
+            self.navigate_to_window(window);
+            self.save_state();
+        }
+    }
+
+    /// Refreshes the entries in the background, every `refresh_interval_ms`.
+    fn refresh_entries_periodically(&mut self) {
+        let interval = self.config.refresh_interval_ms;
+        if interval == 0 || self.last_refresh.elapsed() < Duration::from_millis(interval) {
             return;
         }
+        self.refresh_entries();
+    }
 
-        navigate_to_window(&window);
-        self.last_window_id = Some(window.window_id);
+    /// Updates the title and status of every entry in every list, so the quick menu shows
+    /// current titles and closed and hung windows without having to navigate to them first.
+    /// The quick menu is only updated when an entry of the active list changed.
+    fn refresh_entries(&mut self) {
+        self.last_refresh = Instant::now();
 
-        let _ = self.update_window_title(window.window_id);
-        self.quick_menu.update_state(
-            QuickMenuStateUpdate::new()
-                .with_windows(&self.windows)
-                .with_active_window(window.window_id),
-        );
+        // titles change all the time, e.g. in terminals, so only status changes are saved
+        let mut status_changed = false;
+        let mut active_list_changed = false;
+        let mut closed = vec![];
+        for (index, list) in self.state.lists.iter_mut().enumerate() {
+            for window in list.windows_mut() {
+                let relaunching = self.pending_launches.iter().any(|launch| {
+                    launch.window_id == window.window_id
+                        && launch.process_path == window.process_path
+                });
+                if relaunching {
+                    continue;
+                }
+                match refresh_entry(self.backend.as_ref(), window) {
+                    Refreshed::Nothing => continue,
+                    Refreshed::Title => {}
+                    Refreshed::Status => {
+                        status_changed = true;
+                        if window.status == WindowStatus::Closed {
+                            closed.push(window.window_id);
+                        }
+                    }
+                }
+                active_list_changed |= index == self.state.active_list;
+            }
+        }
+        for window_id in closed {
+            self.mark_closed(window_id);
+        }
+
+        if active_list_changed {
+            self.quick_menu.update_state(
+                QuickMenuStateUpdate::new()
+                    .with_windows(&self.state.lists[self.state.active_list].slots),
+            );
+        }
+        if status_changed {
+            self.save_state();
+        }
+    }
+
+    /// Marks the entries of a window that no longer exists as closed in every list, and gives
+    /// them a closed window id, so they aren't confused with a window that gets the same
+    /// handle later. Returns the new id.
+    fn mark_closed(&mut self, window_id: isize) -> isize {
+        let closed_id = match window::is_closed_id(window_id) {
+            true => window_id,
+            false => window::closed_window_id(),
+        };
+        for list in self.state.lists.iter_mut() {
+            for entry in list.windows_mut().filter(|w| w.window_id == window_id) {
+                entry.window_id = closed_id;
+                entry.status = WindowStatus::Closed;
+            }
+        }
+        self.state.history.replace(window_id, closed_id);
+        self.state.edits.replace(window_id, closed_id);
+        self.state.registers.replace(window_id, closed_id);
+        closed_id
     }
 
     fn update_window_title(&mut self, window_id: isize) -> Result<()> {
-        let title = match get_window_title(window_id) {
+        let title = match self.backend.window_title(window_id) {
             Some(title) => title,
             None => {
                 return Err(anyhow!(
@@ -330,57 +824,302 @@ impl Harpoon {
             }
         };
 
-        let windows = &mut self.windows;
-
-        if let Some(index) = windows.iter().position(|w| w.window_id == window_id) {
-            windows[index].title = title;
+        if let Some(window) = self.state.lists[self.state.active_list].find_mut(window_id) {
+            window.title = title;
         }
 
         Ok(())
     }
 
-    fn swap_windows(&mut self, from_index: usize, to_index: usize) {
-        self.windows.swap(from_index, to_index);
-        let cursor_delta = to_index as isize - from_index as isize;
-        self.quick_menu.update_state(
-            QuickMenuStateUpdate::new()
-                .with_windows(&self.windows)
-                .with_cursor_delta(cursor_delta),
-        );
+    /// Replaces the saved position, size and state of an entry with those of the live window,
+    /// keeping the rest of the entry as it is.
+    fn recapture_geometry(&mut self, live: ApplicationWindow) {
+        let live = self.placed(live);
+        let list = &mut self.state.lists[self.state.active_list];
+        let window = match list.find_mut(live.window_id) {
+            Some(window) => window,
+            None => return,
+        };
+        window.position = live.position;
+        window.state = live.state;
+        window.placement = live.placement;
+        self.quick_menu
+            .update_state(QuickMenuStateUpdate::new().with_windows(&list.slots));
+        self.save_state();
     }
 
-    fn cut_window(&mut self, index: usize) {
-        if self.windows.get(index).is_none() {
-            return;
+    fn recapture_geometry_by_index(&mut self, index: usize) {
+        let window_id = match self.state.lists[self.state.active_list].get(index) {
+            Some(window) => window.window_id,
+            None => return,
+        };
+        let live = self
+            .backend
+            .enumerate_windows()
+            .into_iter()
+            .find(|w| w.window_id == window_id);
+        match live {
+            Some(window) => self.recapture_geometry(window),
+            None => println!("Can't recapture the geometry of a closed window"),
+        }
+    }
+
+    fn save_layout(&mut self, name: &str) {
+        let live = self.backend.enumerate_windows();
+        let windows = self.state.lists[self.state.active_list]
+            .windows()
+            .filter_map(|entry| {
+                let live = live.iter().find(|w| w.window_id == entry.window_id)?;
+                Some(self.placed(ApplicationWindow {
+                    position: live.position.clone(),
+                    state: live.state.clone(),
+                    ..entry.clone()
+                }))
+            })
+            .collect();
+
+        let layout = Layout {
+            name: name.to_string(),
+            windows,
+        };
+        match self.layouts.iter().position(|layout| layout.name == name) {
+            Some(index) => self.layouts[index] = layout,
+            None => self.layouts.push(layout),
+        }
+        self.save_layouts();
+    }
+
+    /// Moves the windows of the active list to their positions in the layout, and keeps those
+    /// positions so navigating to a window doesn't undo the layout.
+    fn apply_layout(&mut self, name: &str) {
+        let layout = match self.layouts.iter().find(|layout| layout.name == name) {
+            Some(layout) => layout,
+            None => {
+                return self.refuse(&format!("There is no layout named {:?}", name));
+            }
+        };
+
+        let alive: Vec<ApplicationWindow> = self.state.lists[self.state.active_list]
+            .windows()
+            .filter(|w| self.backend.is_alive(w.window_id))
+            .cloned()
+            .collect();
+        let windows: Vec<ApplicationWindow> = layout
+            .windows_for(&alive)
+            .into_iter()
+            .map(|window| self.restored(window))
+            .collect();
+        self.backend.move_windows(&windows);
+
+        let list = &mut self.state.lists[self.state.active_list];
+        for window in windows.into_iter() {
+            if let Some(entry) = list.find_mut(window.window_id) {
+                entry.position = window.position;
+                entry.state = window.state;
+                entry.placement = window.placement;
+            }
+        }
+        self.save_state();
+    }
+
+    fn delete_layout(&mut self, name: &str) {
+        let index = match self.layouts.iter().position(|layout| layout.name == name) {
+            Some(index) => index,
+            None => {
+                return self.refuse(&format!("There is no layout named {:?}", name));
+            }
+        };
+        self.layouts.remove(index);
+        self.save_layouts();
+    }
+
+    /// Records which monitor the window is on, so it can be put back on that monitor even if
+    /// the monitor arrangement changes.
+    fn placed(&self, window: ApplicationWindow) -> ApplicationWindow {
+        ApplicationWindow {
+            placement: monitor::place(&window.position, &self.backend.monitors()),
+            ..window
         }
-        let window = self.windows.remove(index);
+    }
+
+    /// Maps the saved position of a window onto the connected monitors.
+    fn restored(&self, window: ApplicationWindow) -> ApplicationWindow {
+        let position = window.placement.as_ref().and_then(|placement| {
+            monitor::restore(
+                placement,
+                &self.backend.monitors(),
+                self.config.fallback_monitor.as_deref(),
+            )
+        });
+        match position {
+            Some(position) => ApplicationWindow { position, ..window },
+            None => window,
+        }
+    }
+
+    fn save_layouts(&self) {
+        if let Err(err) = layout::save_layouts_to_disk(&self.layouts) {
+            println!("Error saving layouts: {}", err);
+        }
+    }
+
+    /// Replaces the windows, name and cursor shown in the quick menu with those of the active list.
+    fn show_active_list(&mut self) {
+        let list = &self.state.lists[self.state.active_list];
         self.quick_menu.update_state(
             QuickMenuStateUpdate::new()
-                .with_windows(&self.windows)
-                .with_cursor_delta(-1),
+                .with_windows(&list.slots)
+                .with_list_name(&list.name)
+                .with_cursor(list.cursor),
         );
-        self.clipboard = Some(window);
     }
 
-    fn paste_window(&mut self, index: usize) {
-        if let Some(window) = self.clipboard.take() {
-            let mut index = index;
-            if index > self.windows.len() {
-                index = self.windows.len();
-            }
-            self.windows.insert(index, window);
-            self.quick_menu.update_state(
-                QuickMenuStateUpdate::new()
-                    .with_windows(&self.windows)
-                    .with_cursor_delta(1),
-            );
+    /// Prints a message and shows it in the quick menu footer.
+    fn show_message(&mut self, message: &str) {
+        println!("{}", message);
+        self.quick_menu
+            .update_state(QuickMenuStateUpdate::new().with_message(message));
+    }
+
+    /// Shows why an event did nothing, which is also what the control socket replies with.
+    fn refuse(&mut self, message: &str) {
+        self.show_message(message);
+        self.refusal = Some(message.to_string());
+    }
+
+    /// Writes the window lists to disk so they can be restored on the next start.
+    fn save_state(&self) {
+        if let Err(err) = state::save_state_to_disk(&self.state.to_persisted()) {
+            println!("Error saving state: {}", err);
         }
+        self.publish_snapshot();
+    }
+
+    /// Updates what the control socket answers queries with.
+    fn publish_snapshot(&self) {
+        let list = self.state.active_list();
+        *self.ipc_snapshot.lock().unwrap() = Snapshot {
+            active_list: list.name.clone(),
+            lists: self.state.lists.iter().map(|l| l.name.clone()).collect(),
+            entries: list.slots.clone(),
+            disable_inhibit: self.disable_inhibit,
+        };
     }
 
     /// Toggles whether keyboard event propagation is inhibited.
     fn toggle_disable_inhibit(&mut self) {
         self.disable_inhibit = !self.disable_inhibit;
+        // hotkeys keep the setting they were registered with, registering them again
+        // replaces them
+        self.register_hooks();
+        self.entry_hotkeys.clear();
+        self.register_entry_hotkeys();
         self.quick_menu
             .update_state(QuickMenuStateUpdate::new().with_disable_inhibit(self.disable_inhibit));
+        self.publish_snapshot();
+    }
+}
+
+/// Returns the status the backend reports for the window of an entry.
+///
+/// An entry that was rebound to a relaunched window stays marked as such for as
+/// long as the new window is alive and responding. Window handles are reused, so
+/// a handle that now belongs to another program means the window was closed.
+fn window_status<B: WindowBackend>(backend: &B, window: &ApplicationWindow) -> WindowStatus {
+    if window::is_closed_id(window.window_id) || !backend.is_alive(window.window_id) {
+        return WindowStatus::Closed;
+    }
+    if backend.window_process_path(window.window_id).as_ref() != Some(&window.process_path) {
+        return WindowStatus::Closed;
+    }
+    if !backend.is_responsive(window.window_id) {
+        return WindowStatus::Unresponsive;
+    }
+    match window.status {
+        WindowStatus::Rebound => WindowStatus::Rebound,
+        _ => WindowStatus::Alive,
+    }
+}
+
+/// What refreshing an entry changed, the status when both changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Refreshed {
+    Nothing,
+    Title,
+    Status,
+}
+
+/// Updates the status and title of an entry from its window, and returns what changed.
+/// Closed windows keep the last title they had.
+fn refresh_entry<B: WindowBackend>(backend: &B, window: &mut ApplicationWindow) -> Refreshed {
+    let status = window_status(backend, window);
+    let title = match status {
+        WindowStatus::Closed => None,
+        _ => backend.window_title(window.window_id),
+    };
+    let title_changed = matches!(&title, Some(title) if *title != window.title);
+    if let Some(title) = title {
+        window.title = title;
+    }
+    if status != window.status {
+        window.status = status;
+        return Refreshed::Status;
+    }
+    match title_changed {
+        true => Refreshed::Title,
+        false => Refreshed::Nothing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{refresh_entry, window_status, Refreshed};
+    use crate::window::{mock::MockBackend, WindowStatus};
+
+    #[test]
+    fn window_status_follows_backend() {
+        let backend = MockBackend::new();
+        let mut window = backend.open_window(1, "/usr/bin/editor", "editor");
+        assert_eq!(window_status(&backend, &window), WindowStatus::Alive);
+
+        backend.set_responsive(1, false);
+        assert_eq!(window_status(&backend, &window), WindowStatus::Unresponsive);
+
+        backend.set_responsive(1, true);
+        window.status = WindowStatus::Rebound;
+        assert_eq!(window_status(&backend, &window), WindowStatus::Rebound);
+
+        backend.close_window(1);
+        assert_eq!(window_status(&backend, &window), WindowStatus::Closed);
+    }
+
+    #[test]
+    fn refreshing_an_entry_reports_changes() {
+        let backend = MockBackend::new();
+        let mut window = backend.open_window(1, "/usr/bin/editor", "editor");
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Nothing);
+
+        backend.set_title(1, "main.rs - editor");
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Title);
+        assert_eq!(window.title, "main.rs - editor");
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Nothing);
+
+        backend.close_window(1);
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Status);
+        assert_eq!(window.status, WindowStatus::Closed);
+        assert_eq!(window.title, "main.rs - editor");
+    }
+
+    #[test]
+    fn a_reused_window_handle_doesnt_revive_an_entry() {
+        let backend = MockBackend::new();
+        let mut window = backend.open_window(1, "/usr/bin/editor", "editor");
+        backend.close_window(1);
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Status);
+
+        backend.open_window(1, "/usr/bin/terminal", "terminal");
+        assert_eq!(window_status(&backend, &window), WindowStatus::Closed);
+        assert_eq!(refresh_entry(&backend, &mut window), Refreshed::Nothing);
+        assert_eq!(window.title, "editor");
     }
 }
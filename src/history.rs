@@ -0,0 +1,120 @@
+/// The harpooned windows that were focused, most recent last.
+///
+/// Windows are remembered by id rather than by their index in a list, so the
+/// history stays valid when entries are swapped, cut or pasted. Going back and
+/// forward moves through the history like a browser does. Focusing a window
+/// in any other way drops the forward part and moves that window to the end.
+#[derive(Debug, Default)]
+pub struct FocusHistory {
+    entries: Vec<isize>,
+    /// the index of the current window in `entries`
+    index: usize,
+    /// the window that was focused before the current one
+    alternate: Option<isize>,
+}
+
+impl FocusHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the window that was focused most recently.
+    pub fn current(&self) -> Option<isize> {
+        self.entries.get(self.index).copied()
+    }
+
+    /// Returns the id of the window that was focused before the current one.
+    pub fn alternate(&self) -> Option<isize> {
+        self.alternate
+    }
+
+    /// Records that the window with the given id was focused.
+    pub fn visit(&mut self, window_id: isize) {
+        if self.current() == Some(window_id) {
+            return;
+        }
+        self.alternate = self.current();
+        self.entries.truncate(self.index + 1);
+        self.entries.retain(|id| *id != window_id);
+        self.entries.push(window_id);
+        self.index = self.entries.len() - 1;
+    }
+
+    /// Moves to the closest older window for which `is_available` returns true.
+    pub fn back(&mut self, is_available: impl Fn(isize) -> bool) -> Option<isize> {
+        let index = (0..self.index)
+            .rev()
+            .find(|index| is_available(self.entries[*index]))?;
+        self.move_to(index)
+    }
+
+    /// Moves to the closest newer window for which `is_available` returns true.
+    pub fn forward(&mut self, is_available: impl Fn(isize) -> bool) -> Option<isize> {
+        let index = (self.index + 1..self.entries.len())
+            .find(|index| is_available(self.entries[*index]))?;
+        self.move_to(index)
+    }
+
+    /// Points the history at the new id of a window, e.g. after it was relaunched.
+    pub fn replace(&mut self, old_id: isize, new_id: isize) {
+        for id in self.entries.iter_mut() {
+            if *id == old_id {
+                *id = new_id;
+            }
+        }
+        if self.alternate == Some(old_id) {
+            self.alternate = Some(new_id);
+        }
+    }
+
+    fn move_to(&mut self, index: usize) -> Option<isize> {
+        self.alternate = self.current();
+        self.index = index;
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FocusHistory;
+
+    #[test]
+    fn back_and_forward_walk_the_history() {
+        let mut history = FocusHistory::new();
+        history.visit(1);
+        history.visit(2);
+        history.visit(3);
+
+        assert_eq!(history.back(|_| true), Some(2));
+        assert_eq!(history.back(|_| true), Some(1));
+        assert_eq!(history.back(|_| true), None);
+        assert_eq!(history.forward(|_| true), Some(2));
+
+        // focusing another window drops the forward part
+        history.visit(4);
+        assert_eq!(history.forward(|_| true), None);
+        assert_eq!(history.back(|_| true), Some(2));
+    }
+
+    #[test]
+    fn back_skips_unavailable_windows() {
+        let mut history = FocusHistory::new();
+        history.visit(1);
+        history.visit(2);
+        history.visit(3);
+
+        assert_eq!(history.back(|id| id != 2), Some(1));
+    }
+
+    #[test]
+    fn alternate_toggles_between_two_windows() {
+        let mut history = FocusHistory::new();
+        history.visit(1);
+        history.visit(2);
+        assert_eq!(history.alternate(), Some(1));
+
+        history.visit(1);
+        assert_eq!(history.alternate(), Some(2));
+        assert_eq!(history.current(), Some(1));
+    }
+}
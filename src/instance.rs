@@ -0,0 +1,114 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+#[cfg(target_os = "linux")]
+use crate::ipc;
+
+/// Held for as long as harpoon runs, so a second launch knows it is already running and
+/// doesn't register every hotkey again.
+///
+/// The lock is taken on the lock file by the OS, which releases it when the process exits,
+/// even if it crashes. The file itself stays behind, removing it would let a second instance
+/// lock a new file while another one still holds the lock on the old one.
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Locks the lock file, or returns `None` if another instance holds the lock.
+pub fn acquire() -> io::Result<Option<InstanceLock>> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path()?)?;
+    if !try_lock(&file)? {
+        return Ok(None);
+    }
+    // only to help finding the instance, the lock doesn't depend on it
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    Ok(Some(InstanceLock { _file: file }))
+}
+
+/// Takes an exclusive lock on the file without waiting, returns whether it got it.
+#[cfg(target_os = "linux")]
+fn try_lock(file: &File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EWOULDBLOCK) => Ok(false),
+        _ => Err(err),
+    }
+}
+
+/// Takes an exclusive lock on the file without waiting, returns whether it got it.
+#[cfg(windows)]
+fn try_lock(file: &File) -> io::Result<bool> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::{
+        Foundation::{ERROR_LOCK_VIOLATION, HANDLE},
+        Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY},
+        System::IO::OVERLAPPED,
+    };
+
+    let handle = HANDLE(file.as_raw_handle() as isize);
+    let mut overlapped = OVERLAPPED::default();
+    let locked = unsafe {
+        LockFileEx(
+            handle,
+            LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+            0,
+            1,
+            0,
+            &mut overlapped,
+        )
+    };
+    if locked.as_bool() {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(code) if code == ERROR_LOCK_VIOLATION.0 as i32 => Ok(false),
+        _ => Err(err),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn lock_path() -> io::Result<PathBuf> {
+    Ok(ipc::socket_path()?.with_extension("lock"))
+}
+
+#[cfg(windows)]
+fn lock_path() -> io::Result<PathBuf> {
+    Ok(std::env::temp_dir().join("harpoon-rs.lock"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::try_lock;
+
+    #[test]
+    fn only_one_lock_is_granted() {
+        let path =
+            std::env::temp_dir().join(format!("harpoon-rs-test-{}.lock", std::process::id()));
+        let first = File::create(&path).unwrap();
+        let second = File::open(&path).unwrap();
+        assert!(try_lock(&first).unwrap());
+        assert!(!try_lock(&second).unwrap());
+
+        // the lock goes away with the file that holds it
+        drop(first);
+        assert!(try_lock(&second).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,621 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{harpoon::HarpoonEvent, window::ApplicationWindow};
+
+/// A line sent to the control socket, either a query or an event.
+///
+/// Events are serialized the same way as in the config and in recordings, e.g.
+/// `"NavigateToNextWindow"` or `{"NavigateToWindowByIndex":2}`. Queries are sent as
+/// `{"query":"ListEntries"}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Request {
+    Query { query: Query },
+    Event(HarpoonEvent),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Query {
+    /// The entries of the active list
+    ListEntries,
+    /// The name of the active list and the names of all lists
+    GetActiveList,
+    /// Whether keyboard events are passed on to other applications
+    GetInhibitState,
+}
+
+/// What the control socket answers queries with, kept up to date by `Harpoon`.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub active_list: String,
+    pub lists: Vec<String>,
+    pub entries: Vec<Option<ApplicationWindow>>,
+    pub disable_inhibit: bool,
+}
+
+/// The clients waiting for the events they sent to be handled, in the order the events were
+/// sent. Harpoon takes the reply of each event it handles and sends it the result.
+#[derive(Clone, Default)]
+pub struct Replies(Arc<Mutex<VecDeque<(HarpoonEvent, Reply)>>>);
+
+/// Where to send the result of handling an event, `Err` with the message harpoon showed.
+pub type Reply = Sender<Result<(), String>>;
+
+impl Replies {
+    /// Sends the event through the channel the hotkeys use and waits for harpoon to handle it.
+    fn send_and_wait(
+        &self,
+        event_sender: &Arc<Mutex<Sender<HarpoonEvent>>>,
+        event: HarpoonEvent,
+    ) -> Result<(), String> {
+        let (reply, result) = mpsc::channel();
+        {
+            // the reply is queued while holding the sender, so the replies are in the same
+            // order as the events in the channel
+            let event_sender = event_sender.lock().unwrap();
+            self.0.lock().unwrap().push_back((event.clone(), reply));
+            if let Err(err) = event_sender.send(event) {
+                self.0.lock().unwrap().pop_back();
+                return Err(err.to_string());
+            }
+        }
+        result
+            .recv()
+            .unwrap_or_else(|_| Err("harpoon stopped before handling the event".to_string()))
+    }
+
+    /// Returns where to send the result of an event that is about to be handled, if it was
+    /// sent to the control socket.
+    ///
+    /// A hotkey may send the same event just before a client does, in which case that one
+    /// takes the reply. Both do the same, so the client still gets the right answer.
+    pub fn take(&self, event: &HarpoonEvent) -> Option<Reply> {
+        let mut replies = self.0.lock().unwrap();
+        match replies.front() {
+            Some((waiting_for, _)) if waiting_for == event => replies.pop_front().map(|r| r.1),
+            _ => None,
+        }
+    }
+}
+
+/// Answers a single request. Events are handled by harpoon exactly like pressing their
+/// hotkey would, and the reply waits until they are, so it can tell whether they did anything.
+pub fn respond(
+    line: &str,
+    event_sender: &Arc<Mutex<Sender<HarpoonEvent>>>,
+    replies: &Replies,
+    snapshot: &Arc<Mutex<Snapshot>>,
+) -> Value {
+    let request = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request,
+        Err(err) => return json!({ "error": format!("invalid request: {}", err) }),
+    };
+
+    match request {
+        Request::Event(event) => match replies.send_and_wait(event_sender, event) {
+            Ok(()) => json!({ "ok": true }),
+            Err(message) => json!({ "error": message }),
+        },
+        Request::Query { query } => {
+            let snapshot = snapshot.lock().unwrap();
+            match query {
+                Query::ListEntries => json!({
+                    "list": snapshot.active_list,
+                    "entries": snapshot.entries,
+                }),
+                Query::GetActiveList => json!({
+                    "active_list": snapshot.active_list,
+                    "lists": snapshot.lists,
+                }),
+                Query::GetInhibitState => json!({
+                    "disable_inhibit": snapshot.disable_inhibit,
+                }),
+            }
+        }
+    }
+}
+
+/// What the threads serving connections share.
+#[derive(Clone)]
+pub struct Server {
+    pub event_sender: Arc<Mutex<Sender<HarpoonEvent>>>,
+    pub replies: Replies,
+    pub snapshot: Arc<Mutex<Snapshot>>,
+    /// how many connections are being served
+    connections: Arc<AtomicUsize>,
+}
+
+/// How many connections are served at the same time, more are turned away.
+const MAX_CONNECTIONS: usize = 8;
+
+impl Server {
+    pub fn new(
+        event_sender: Arc<Mutex<Sender<HarpoonEvent>>>,
+        replies: Replies,
+        snapshot: Arc<Mutex<Snapshot>>,
+    ) -> Self {
+        Server {
+            event_sender,
+            replies,
+            snapshot,
+            connections: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Serves the connection on a thread of its own, unless there are too many already.
+    fn spawn<S: Read + Write + Send + 'static>(&self, mut stream: S) {
+        if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            self.connections.fetch_sub(1, Ordering::SeqCst);
+            let _ = writeln!(stream, "{}", json!({ "error": "too many connections" }));
+            return;
+        }
+        let server = self.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = serve(stream, &server) {
+                println!("Error serving IPC connection: {}", err);
+            }
+            server.connections.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Reads requests from a connection line by line and writes a JSON reply for each.
+fn serve<S: Read + Write>(stream: S, server: &Server) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = respond(
+            line.trim(),
+            &server.event_sender,
+            &server.replies,
+            &server.snapshot,
+        );
+        let stream = reader.get_mut();
+        writeln!(stream, "{}", reply)?;
+        stream.flush()?;
+    }
+}
+
+/// The path of the control socket, in `$XDG_RUNTIME_DIR` when it is set.
+///
+/// Otherwise it is in a directory of its own in the temp dir, which only the current user
+/// can access, so other users can't send events to harpoon or read the window titles.
+#[cfg(target_os = "linux")]
+pub fn socket_path() -> io::Result<std::path::PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => {
+            let uid = unsafe { libc::getuid() };
+            let dir = std::env::temp_dir().join(format!("harpoon-rs-{}", uid));
+            create_private_dir(&dir, uid)?;
+            dir
+        }
+    };
+    Ok(dir.join("harpoon-rs.sock"))
+}
+
+/// Creates a directory only its owner can access, or checks that the existing one is.
+#[cfg(target_os = "linux")]
+fn create_private_dir(dir: &std::path::Path, uid: u32) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+    // someone else may have created it first, hoping we use it anyway
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a private directory of the current user",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Starts accepting connections on the control socket on a background thread.
+#[cfg(target_os = "linux")]
+pub fn listen(server: Server) -> io::Result<()> {
+    use std::os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    };
+
+    let path = socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("another instance is listening on {}", path.display()),
+        ));
+    }
+    // a socket left behind by an instance that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    println!("Error accepting IPC connection: {}", err);
+                    continue;
+                }
+            };
+            server.spawn(stream);
+        }
+    });
+    Ok(())
+}
+
+/// Removes the control socket, so clients don't try to connect to an instance that is gone.
+#[cfg(target_os = "linux")]
+pub fn stop_listening() {
+    if let Ok(path) = socket_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Sends a single request to the running instance and returns its reply.
+#[cfg(target_os = "linux")]
+pub fn request(request: &Request) -> io::Result<Value> {
+    let stream = std::os::unix::net::UnixStream::connect(socket_path()?)?;
+    exchange(stream, request)
+}
+
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\harpoon-rs";
+
+/// Starts accepting connections on the control pipe on a background thread.
+#[cfg(windows)]
+pub fn listen(server: Server) -> io::Result<()> {
+    use windows::{
+        core::HSTRING,
+        Win32::{
+            Foundation::{
+                CloseHandle, GetLastError, ERROR_PIPE_CONNECTED, HLOCAL, INVALID_HANDLE_VALUE,
+            },
+            Security::SECURITY_ATTRIBUTES,
+            Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX},
+            System::{
+                Memory::LocalFree,
+                Pipes::{
+                    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE,
+                    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES,
+                    PIPE_WAIT,
+                },
+            },
+        },
+    };
+
+    let name = HSTRING::from(PIPE_NAME);
+    let create_pipe = move |first: bool| {
+        // the first instance fails if another harpoon already created the pipe
+        let open_mode = match first {
+            true => PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            false => PIPE_ACCESS_DUPLEX,
+        };
+        let descriptor = current_user_only()?;
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor.0,
+            bInheritHandle: false.into(),
+        };
+        let handle = unsafe {
+            CreateNamedPipeW(
+                &name,
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                Some(&attributes),
+            )
+        };
+        let _ = unsafe { LocalFree(HLOCAL(descriptor.0 as isize)) };
+        if handle == INVALID_HANDLE_VALUE {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(handle)
+        }
+    };
+    // create the first instance up front, so failing to create the pipe is reported
+    let mut handle = create_pipe(true)?;
+
+    std::thread::spawn(move || loop {
+        let connected = unsafe { ConnectNamedPipe(handle, None) }.as_bool()
+            || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+        if connected {
+            server.spawn(PipeStream(handle));
+        } else {
+            unsafe { CloseHandle(handle) };
+        }
+
+        handle = match create_pipe(false) {
+            Ok(handle) => handle,
+            Err(err) => {
+                println!("Error creating IPC pipe: {}", err);
+                return;
+            }
+        };
+    });
+    Ok(())
+}
+
+/// A security descriptor that only gives the current user access, so other users can't send
+/// events to harpoon or read the window titles. It has to be freed with `LocalFree`.
+#[cfg(windows)]
+fn current_user_only() -> io::Result<windows::Win32::Security::PSECURITY_DESCRIPTOR> {
+    use windows::{
+        core::{HSTRING, PWSTR},
+        Win32::{
+            Foundation::{CloseHandle, HANDLE, HLOCAL},
+            Security::{
+                Authorization::{
+                    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW,
+                    SDDL_REVISION_1,
+                },
+                GetTokenInformation, TokenUser, PSECURITY_DESCRIPTOR, TOKEN_QUERY, TOKEN_USER,
+            },
+            System::{
+                Memory::LocalFree,
+                Threading::{GetCurrentProcess, OpenProcessToken},
+            },
+        },
+    };
+
+    unsafe {
+        let mut token = HANDLE::default();
+        if !OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).as_bool() {
+            return Err(io::Error::last_os_error());
+        }
+        let mut length = 0;
+        GetTokenInformation(token, TokenUser, None, 0, &mut length);
+        // a u64 buffer, so the TOKEN_USER at its start is aligned
+        let mut buffer = vec![0u64; (length as usize + 7) / 8];
+        let ok = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+            length,
+            &mut length,
+        );
+        CloseHandle(token);
+        if !ok.as_bool() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        if !ConvertSidToStringSidW(user.User.Sid, &mut sid).as_bool() {
+            return Err(io::Error::last_os_error());
+        }
+        let sid_string = sid.to_string();
+        let _ = LocalFree(HLOCAL(sid.0 as isize));
+        let sid_string =
+            sid_string.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        // full access for the user and nobody else, without inheriting other entries
+        let sddl = HSTRING::from(format!("D:P(A;;GA;;;{})", sid_string));
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        if !ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            &sddl,
+            SDDL_REVISION_1,
+            &mut descriptor,
+            None,
+        )
+        .as_bool()
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(descriptor)
+    }
+}
+
+/// The pipe goes away together with the process, there is nothing to clean up.
+#[cfg(windows)]
+pub fn stop_listening() {}
+
+/// Sends a single request to the running instance and returns its reply.
+#[cfg(windows)]
+pub fn request(request: &Request) -> io::Result<Value> {
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(PIPE_NAME)?;
+    exchange(pipe, request)
+}
+
+/// Writes the request as a single line and reads the line that is sent back.
+fn exchange<S: Read + Write>(mut stream: S, request: &Request) -> io::Result<Value> {
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+    stream.flush()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(serde_json::from_str(&reply)?)
+}
+
+/// A connected instance of the control pipe.
+#[cfg(windows)]
+struct PipeStream(windows::Win32::Foundation::HANDLE);
+
+#[cfg(windows)]
+impl Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use windows::Win32::{Foundation::ERROR_BROKEN_PIPE, Storage::FileSystem::ReadFile};
+
+        let mut read = 0;
+        let ok = unsafe {
+            ReadFile(
+                self.0,
+                Some(buf.as_mut_ptr() as *mut std::ffi::c_void),
+                buf.len() as u32,
+                Some(&mut read),
+                None,
+            )
+        };
+        if !ok.as_bool() {
+            let err = io::Error::last_os_error();
+            // the client closed its end of the pipe
+            if err.raw_os_error() == Some(ERROR_BROKEN_PIPE.0 as i32) {
+                return Ok(0);
+            }
+            return Err(err);
+        }
+        Ok(read as usize)
+    }
+}
+
+#[cfg(windows)]
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        use windows::Win32::Storage::FileSystem::WriteFile;
+
+        let mut written = 0;
+        let ok = unsafe { WriteFile(self.0, Some(buf), Some(&mut written), None) };
+        if !ok.as_bool() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        use windows::Win32::Storage::FileSystem::FlushFileBuffers;
+
+        unsafe { FlushFileBuffers(self.0) };
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl Drop for PipeStream {
+    fn drop(&mut self) {
+        use windows::Win32::{Foundation::CloseHandle, System::Pipes::DisconnectNamedPipe};
+
+        unsafe {
+            DisconnectNamedPipe(self.0);
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use super::{respond, Replies, Snapshot};
+    use crate::harpoon::HarpoonEvent;
+
+    #[test]
+    fn events_are_forwarded_and_queries_answered() {
+        let (sender, receiver) = mpsc::channel::<HarpoonEvent>();
+        let sender = Arc::new(Mutex::new(sender));
+        let replies = Replies::default();
+        let snapshot = Arc::new(Mutex::new(Snapshot {
+            active_list: "work".to_string(),
+            lists: vec!["default".to_string(), "work".to_string()],
+            entries: vec![None],
+            disable_inhibit: true,
+        }));
+
+        // stands in for harpoon, which only has the one entry
+        let (handled_sender, handled) = mpsc::channel();
+        let harpoon_replies = replies.clone();
+        std::thread::spawn(move || {
+            for event in receiver {
+                let reply = harpoon_replies.take(&event);
+                let result = match event {
+                    HarpoonEvent::NavigateToWindowByIndex(0) => Ok(()),
+                    HarpoonEvent::NavigateToWindowByIndex(i) => {
+                        Err(format!("Slot {} is empty", i + 1))
+                    }
+                    _ => Ok(()),
+                };
+                handled_sender.send(event).unwrap();
+                if let Some(reply) = reply {
+                    reply.send(result).unwrap();
+                }
+            }
+        });
+
+        // a hotkey pressed before the client sends its events doesn't take their replies
+        let hotkey = HarpoonEvent::NavigateToWindowByIndex(4);
+        sender.lock().unwrap().send(hotkey.clone()).unwrap();
+        let reply = respond(
+            r#"{"NavigateToWindowByIndex":0}"#,
+            &sender,
+            &replies,
+            &snapshot,
+        );
+        assert_eq!(reply.to_string(), r#"{"ok":true}"#);
+        let reply = respond(
+            r#"{"NavigateToWindowByIndex":2}"#,
+            &sender,
+            &replies,
+            &snapshot,
+        );
+        assert_eq!(reply.to_string(), r#"{"error":"Slot 3 is empty"}"#);
+        assert_eq!(
+            handled.try_iter().collect::<Vec<_>>(),
+            vec![
+                hotkey,
+                HarpoonEvent::NavigateToWindowByIndex(0),
+                HarpoonEvent::NavigateToWindowByIndex(2)
+            ]
+        );
+
+        let reply = respond(r#"{"query":"GetActiveList"}"#, &sender, &replies, &snapshot);
+        assert_eq!(
+            reply.to_string(),
+            r#"{"active_list":"work","lists":["default","work"]}"#
+        );
+        let reply = respond(r#"{"query":"ListEntries"}"#, &sender, &replies, &snapshot);
+        assert_eq!(reply.to_string(), r#"{"entries":[null],"list":"work"}"#);
+
+        let reply = respond("NavigateToNextWindow", &sender, &replies, &snapshot);
+        assert!(reply.get("error").is_some());
+        assert!(handled.try_recv().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn socket_dir_must_be_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let uid = unsafe { libc::getuid() };
+        let dir = std::env::temp_dir().join(format!("harpoon-rs-test-{}", std::process::id()));
+        super::create_private_dir(&dir, uid).unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        // an existing private directory is fine
+        super::create_private_dir(&dir, uid).unwrap();
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(super::create_private_dir(&dir, uid).is_err());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
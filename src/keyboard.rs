@@ -294,6 +294,11 @@ impl FltkKeyCombination {
         if self.keys != event_key || self.modifiers != event_state {
             return false;
         }
+        // with Ctrl held, fltk reports a control character as the text, e.g. "\x12" for
+        // Ctrl+R, so there is nothing to compare the text with
+        if self.modifiers.contains(fltk::enums::Shortcut::Ctrl) {
+            return true;
+        }
 
         let text = &self.text;
 
@@ -310,3 +315,64 @@ impl FltkKeyCombination {
         return true;
     }
 }
+
+/// Converts a key pressed in the quick menu to the keys of a global hotkey, modifiers first.
+///
+/// fltk doesn't tell the left and right modifiers apart, so the left ones are used like in
+/// the config. Returns `None` for keys that aren't a letter, a digit or a function key.
+pub fn mki_keys_from_fltk(
+    key: fltk::enums::Key,
+    state: fltk::enums::Shortcut,
+) -> Option<Vec<mki::Keyboard>> {
+    let name = match key.bits() {
+        0x61..=0x7a | 0x30..=0x39 => key.to_char()?.to_ascii_uppercase().to_string(),
+        bits if (fltk::enums::Key::F1.bits()..=fltk::enums::Key::F12.bits()).contains(&bits) => {
+            format!("F{}", bits - fltk::enums::Key::F1.bits() + 1)
+        }
+        _ => return None,
+    };
+    let key = name.parse::<mki::Keyboard>().ok()?;
+
+    let mut keys = vec![];
+    if state.contains(fltk::enums::Shortcut::Ctrl) {
+        keys.push(mki::Keyboard::LeftControl);
+    }
+    if state.contains(fltk::enums::Shortcut::Alt) {
+        keys.push(mki::Keyboard::LeftAlt);
+    }
+    if state.contains(fltk::enums::Shortcut::Shift) {
+        keys.push(mki::Keyboard::LeftShift);
+    }
+    if state.contains(fltk::enums::Shortcut::Meta) {
+        keys.push(mki::Keyboard::LeftWindows);
+    }
+    keys.push(key);
+    Some(keys)
+}
+
+/// Formats a hotkey the way the config names its keys, e.g. `LeftControl + E`.
+pub fn hotkey_string(hotkey: &[mki::Keyboard]) -> String {
+    hotkey
+        .iter()
+        .map(|key| format!("{:?}", key))
+        .collect::<Vec<String>>()
+        .join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use fltk::enums::{Key, Shortcut};
+    use mki::Keyboard;
+
+    use super::FltkKeyCombination;
+
+    #[test]
+    fn ctrl_combinations_ignore_the_control_character() {
+        let redo = FltkKeyCombination::from_mki_vec(&vec![Keyboard::LeftControl, Keyboard::R]);
+        assert!(redo.is_triggered(Key::from_char('r'), Shortcut::Ctrl, "\x12"));
+        assert!(!redo.is_triggered(Key::from_char('r'), Shortcut::None, "r"));
+
+        let undo = FltkKeyCombination::from_mki_vec(&vec![Keyboard::U]);
+        assert!(undo.is_triggered(Key::from_char('u'), Shortcut::None, "u"));
+    }
+}
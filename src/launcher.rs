@@ -0,0 +1,103 @@
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+    config::{LaunchOptions, RunOrRaiseRule},
+    window::{ApplicationWindow, WindowBackend},
+};
+
+/// How long to wait for a launched program to open a window.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A program that was launched for a closed window or a run-or-raise rule, waiting for its
+/// new window to appear.
+#[derive(Debug)]
+pub struct PendingLaunch {
+    /// the id of the window that was closed
+    pub window_id: isize,
+    pub process_path: String,
+    /// the rule the program was launched for, its window is focused instead of bound to an
+    /// entry
+    pub rule: Option<RunOrRaiseRule>,
+    /// windows of the program that were already open before it was launched
+    known_windows: Vec<isize>,
+    deadline: Instant,
+}
+
+impl PendingLaunch {
+    /// Launches the program of a closed window.
+    pub fn start<B: WindowBackend>(
+        window: &ApplicationWindow,
+        options: Option<&LaunchOptions>,
+        backend: &B,
+    ) -> Result<Self> {
+        let known_windows = backend
+            .enumerate_process_windows(&window.process_path)
+            .into_iter()
+            .map(|w| w.window_id)
+            .collect();
+
+        let mut command = Command::new(&window.process_path);
+        if let Some(options) = options {
+            command.args(&options.arguments);
+            if let Some(working_directory) = &options.working_directory {
+                command.current_dir(working_directory);
+            }
+        }
+        command.spawn()?;
+
+        Ok(Self {
+            window_id: window.window_id,
+            process_path: window.process_path.clone(),
+            rule: None,
+            known_windows,
+            deadline: Instant::now() + LAUNCH_TIMEOUT,
+        })
+    }
+
+    /// Launches the command of a run-or-raise rule.
+    pub fn run<B: WindowBackend>(rule: &RunOrRaiseRule, backend: &B) -> Result<Self> {
+        let known_windows = backend
+            .enumerate_windows()
+            .into_iter()
+            .filter(|w| rule.matches(w))
+            .map(|w| w.window_id)
+            .collect();
+
+        let mut command = Command::new(&rule.command);
+        command.args(&rule.arguments);
+        if let Some(working_directory) = &rule.working_directory {
+            command.current_dir(working_directory);
+        }
+        command.spawn()?;
+
+        Ok(Self {
+            window_id: 0,
+            process_path: rule.command.clone(),
+            rule: Some(rule.clone()),
+            known_windows,
+            deadline: Instant::now() + LAUNCH_TIMEOUT,
+        })
+    }
+
+    /// Returns the first top-level window of the program that wasn't open before it was
+    /// launched. For a rule, that is the first new window that matches it, since the command
+    /// may well be a script that starts another program.
+    pub fn find_window<B: WindowBackend>(&self, backend: &B) -> Option<ApplicationWindow> {
+        backend.enumerate_windows().into_iter().find(|w| {
+            let matches = match &self.rule {
+                Some(rule) => rule.matches(w),
+                None => w.process_path == self.process_path,
+            };
+            matches && !self.known_windows.contains(&w.window_id)
+        })
+    }
+
+    pub fn has_expired(&self) -> bool {
+        Instant::now() > self.deadline
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::rematch_windows,
+    window::{self, ApplicationWindow},
+};
+
+const LAYOUTS_FILE_PATH: &str = "layouts.json";
+
+/// A named snapshot of the positions, sizes and states of the windows in a list,
+/// e.g. one for the laptop screen and one for when it is docked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layout {
+    pub name: String,
+    pub windows: Vec<ApplicationWindow>,
+}
+
+impl Layout {
+    /// Returns the saved windows that belong to one of the given entries, with the window id
+    /// of that entry.
+    ///
+    /// Windows are matched by id first, which works as long as harpoon hasn't been restarted
+    /// since the layout was saved. The remaining windows are matched by process path and
+    /// title like stored windows are on start up.
+    pub fn windows_for(&self, entries: &[ApplicationWindow]) -> Vec<ApplicationWindow> {
+        let mut matched: Vec<ApplicationWindow> = vec![];
+        let mut unmatched = vec![];
+        for window in self.windows.iter() {
+            let same_window = entries.iter().any(|entry| {
+                entry.window_id == window.window_id && entry.process_path == window.process_path
+            });
+            match same_window {
+                true => matched.push(window.clone()),
+                false => unmatched.push(window.clone()),
+            }
+        }
+
+        let unclaimed: Vec<ApplicationWindow> = entries
+            .iter()
+            .filter(|entry| !matched.iter().any(|w| w.window_id == entry.window_id))
+            .cloned()
+            .collect();
+        matched.extend(
+            rematch_windows(unmatched, &unclaimed)
+                .into_iter()
+                .filter(|w| !window::is_closed_id(w.window_id)),
+        );
+        matched
+    }
+}
+
+pub fn load_layouts_from_disk() -> Result<Vec<Layout>> {
+    if !std::path::Path::new(LAYOUTS_FILE_PATH).exists() {
+        return Ok(vec![]);
+    }
+    let layouts = std::fs::read_to_string(LAYOUTS_FILE_PATH)?;
+    Ok(serde_json::from_str(&layouts)?)
+}
+
+pub fn save_layouts_to_disk(layouts: &[Layout]) -> Result<()> {
+    let layouts = serde_json::to_string_pretty(layouts)?;
+    std::fs::write(LAYOUTS_FILE_PATH, layouts)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layout;
+    use crate::window::{mock::MockBackend, WindowBackend, WindowPosition};
+
+    #[test]
+    fn layout_matches_entries_by_id_then_by_process() {
+        let before_restart = MockBackend::new();
+        let mut saved = vec![
+            before_restart.open_window(1, "/usr/bin/terminal", "build"),
+            before_restart.open_window(2, "/usr/bin/editor", "notes"),
+            before_restart.open_window(3, "/usr/bin/browser", "docs"),
+        ];
+        saved[1].position = WindowPosition {
+            x: 100.0,
+            y: 0.0,
+            width: 400.0,
+            height: 300.0,
+        };
+        let layout = Layout {
+            name: "docked".to_string(),
+            windows: saved,
+        };
+
+        // the terminal kept its id, the editor was restarted and the browser is gone
+        let backend = MockBackend::new();
+        backend.open_window(1, "/usr/bin/terminal", "build");
+        backend.open_window(20, "/usr/bin/editor", "notes");
+
+        let windows = layout.windows_for(&backend.enumerate_windows());
+
+        let ids: Vec<isize> = windows.iter().map(|w| w.window_id).collect();
+        assert_eq!(ids, vec![1, 20]);
+        assert_eq!(windows[1].position.x, 100.0);
+
+        backend.move_windows(&windows);
+        let moved = backend.enumerate_windows();
+        assert_eq!(moved[1].position.width, 400.0);
+    }
+}
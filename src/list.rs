@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::window::ApplicationWindow;
+
+/// The name of the list that is created when there are no lists yet.
+pub const DEFAULT_LIST_NAME: &str = "default";
+
+/// A named list of harpooned windows.
+///
+/// Windows are pinned to numbered slots, so removing a window leaves its slot
+/// empty instead of moving the windows after it to other slot hotkeys. Every
+/// list keeps its own quick menu cursor and clipboard, so switching between
+/// lists picks up where the list was left.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarpoonList {
+    pub name: String,
+    /// the slots of the list, there are never any empty slots at the end
+    #[serde(alias = "windows")]
+    pub slots: Vec<Option<ApplicationWindow>>,
+    /// the position of the quick menu cursor while this list is active
+    #[serde(skip)]
+    pub cursor: isize,
+    /// the unnamed register, which holds the windows that were last cut or yanked in this list
+    #[serde(skip)]
+    pub clipboard: Vec<ApplicationWindow>,
+}
+
+impl HarpoonList {
+    pub fn new(name: &str) -> Self {
+        Self::with_windows(name, vec![])
+    }
+
+    /// Creates a list with the windows in consecutive slots.
+    pub fn with_windows(name: &str, windows: Vec<ApplicationWindow>) -> Self {
+        Self {
+            name: name.to_string(),
+            slots: windows.into_iter().map(Some).collect(),
+            cursor: 0,
+            clipboard: vec![],
+        }
+    }
+
+    /// Returns the windows in the list, skipping empty slots.
+    pub fn windows(&self) -> impl Iterator<Item = &ApplicationWindow> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut ApplicationWindow> {
+        self.slots.iter_mut().flatten()
+    }
+
+    /// Returns the window in the given slot, if the slot exists and isn't empty.
+    pub fn get(&self, slot: usize) -> Option<&ApplicationWindow> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, slot: usize) -> Option<&mut ApplicationWindow> {
+        self.slots.get_mut(slot)?.as_mut()
+    }
+
+    /// Returns the slot of the window with the given id.
+    pub fn position(&self, window_id: isize) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| matches!(slot, Some(w) if w.window_id == window_id))
+    }
+
+    pub fn find_mut(&mut self, window_id: isize) -> Option<&mut ApplicationWindow> {
+        self.windows_mut().find(|w| w.window_id == window_id)
+    }
+
+    /// Returns the first empty slot, which is the slot after the last window if there are no
+    /// gaps.
+    pub fn first_empty_slot(&self) -> usize {
+        self.slots
+            .iter()
+            .position(|slot| slot.is_none())
+            .unwrap_or(self.slots.len())
+    }
+
+    /// Puts a window in a slot, or empties the slot, and returns what was in it before.
+    pub fn set(
+        &mut self,
+        slot: usize,
+        window: Option<ApplicationWindow>,
+    ) -> Option<ApplicationWindow> {
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, None);
+        }
+        let previous = std::mem::replace(&mut self.slots[slot], window);
+        self.trim();
+        previous
+    }
+
+    /// Inserts a window before the given slot, moving the windows from that slot on down.
+    pub fn insert(&mut self, slot: usize, window: ApplicationWindow) {
+        let slot = slot.min(self.slots.len());
+        self.slots.insert(slot, Some(window));
+    }
+
+    /// Swaps the contents of two slots.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a.max(b) >= self.slots.len() {
+            return;
+        }
+        self.slots.swap(a, b);
+        self.trim();
+    }
+
+    /// Removes the empty slots at the end of the list.
+    fn trim(&mut self) {
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HarpoonList;
+    use crate::window::mock::MockBackend;
+
+    #[test]
+    fn emptied_slots_keep_the_other_windows_in_place() {
+        let backend = MockBackend::new();
+        let mut list = HarpoonList::with_windows(
+            "test",
+            vec![
+                backend.open_window(1, "/usr/bin/editor", "editor"),
+                backend.open_window(2, "/usr/bin/terminal", "terminal"),
+                backend.open_window(3, "/usr/bin/browser", "browser"),
+            ],
+        );
+
+        list.set(1, None);
+        assert_eq!(list.get(2).map(|w| w.window_id), Some(3));
+        assert_eq!(list.first_empty_slot(), 1);
+
+        // emptying the last slot drops all empty slots at the end
+        list.set(2, None);
+        assert_eq!(list.slots.len(), 1);
+
+        list.set(4, Some(backend.open_window(5, "/usr/bin/notes", "notes")));
+        assert_eq!(list.slots.len(), 5);
+        assert_eq!(list.position(5), Some(4));
+    }
+}
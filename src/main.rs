@@ -4,19 +4,127 @@
 )]
 use harpoon::Harpoon;
 
+#[cfg(windows)]
 mod assets;
+mod cli;
 mod config;
 mod harpoon;
+mod history;
+mod instance;
+mod ipc;
 mod keyboard;
+mod launcher;
+mod layout;
+mod list;
+mod monitor;
+#[cfg(windows)]
 mod notification;
 mod quick_menu;
+mod recorder;
+mod reducer;
+mod register;
+mod replay;
+mod state;
+mod undo;
 mod window;
 
 // use anyhow macros
 #[macro_use]
 extern crate anyhow;
 
+/// Replays a recording instead of starting harpoon when it is started with
+/// `--replay <recording> [<expected state file>]`, and returns whether it did.
+fn replay_from_args() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("--replay") {
+        return false;
+    }
+    let recording = match args.get(1) {
+        Some(recording) => recording,
+        None => {
+            println!("Usage: harpoon-rs --replay <recording> [<expected state file>]");
+            std::process::exit(2);
+        }
+    };
+    match replay::run(recording, args.get(2).map(String::as_str)) {
+        Ok(true) => true,
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            println!("Error replaying {}: {}", recording, err);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Sends a command to the running instance instead of starting harpoon when it is started
+/// with one, e.g. `harpoon-rs goto 2`, and returns whether it did.
+fn command_from_args() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, json) = match cli::parse(&args) {
+        Ok(Some(command)) => command,
+        Ok(None) => return false,
+        Err(err) => {
+            println!("{}\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if !cli::run(&command, json) {
+        std::process::exit(1);
+    }
+    true
+}
+
+/// Makes sure only one instance of harpoon runs, exits when another one already does.
+fn lock_instance() -> Option<instance::InstanceLock> {
+    match instance::acquire() {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            println!("harpoon-rs is already running, use `harpoon-rs menu` to open its quick menu or `harpoon-rs quit` to stop it");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            println!("Error locking the lock file: {}", err);
+            None
+        }
+    }
+}
+
+/// Release builds on Windows don't get a console of their own, so without this whatever the
+/// subcommands print would be lost. Writes it to the console harpoon was started from.
+#[cfg(windows)]
+fn attach_to_parent_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // fails when there already is a console, as in debug builds, which is fine
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(windows)]
+fn main() {
+    if std::env::args().len() > 1 {
+        attach_to_parent_console();
+    }
+    if replay_from_args() || command_from_args() {
+        return;
+    }
+    let _lock = lock_instance();
+    let mut harpoon = Harpoon::new(window::Win32Backend::new());
+    harpoon.run();
+}
+
+#[cfg(target_os = "linux")]
 fn main() {
-    let mut harpoon = Harpoon::new();
+    if replay_from_args() || command_from_args() {
+        return;
+    }
+    let _lock = lock_instance();
+    let backend = match window::X11Backend::new() {
+        Ok(backend) => backend,
+        Err(e) => {
+            println!("Error connecting to the X server: {}", e);
+            return;
+        }
+    };
+    let mut harpoon = Harpoon::new(backend);
     harpoon.run();
 }
@@ -0,0 +1,145 @@
+use crate::window::{Monitor, MonitorPlacement, WindowPosition};
+
+/// Returns where a window is placed relative to the monitor it is on.
+///
+/// A window is on the monitor whose work area contains its center, or the
+/// monitor closest to its center when it is outside of every work area.
+pub fn place(position: &WindowPosition, monitors: &[Monitor]) -> Option<MonitorPlacement> {
+    let (center_x, center_y) = (
+        position.x + position.width / 2.0,
+        position.y + position.height / 2.0,
+    );
+    let monitor = monitors.iter().min_by(|a, b| {
+        distance(&a.work_area, center_x, center_y).total_cmp(&distance(
+            &b.work_area,
+            center_x,
+            center_y,
+        ))
+    })?;
+
+    let work_area = &monitor.work_area;
+    Some(MonitorPlacement {
+        monitor: monitor.clone(),
+        position: WindowPosition {
+            x: (position.x - work_area.x) / monitor.scale,
+            y: (position.y - work_area.y) / monitor.scale,
+            width: position.width / monitor.scale,
+            height: position.height / monitor.scale,
+        },
+    })
+}
+
+/// Returns the absolute position of a placed window.
+///
+/// The window goes back to the monitor it was placed on. If that monitor is
+/// not connected, it goes to the fallback monitor, then the primary monitor,
+/// and then whichever monitor is left. The window is scaled to the DPI of the
+/// monitor it ends up on and clamped to that monitor's work area.
+pub fn restore(
+    placement: &MonitorPlacement,
+    monitors: &[Monitor],
+    fallback_monitor: Option<&str>,
+) -> Option<WindowPosition> {
+    let monitor = monitors
+        .iter()
+        .find(|m| m.id == placement.monitor.id)
+        .or_else(|| {
+            monitors
+                .iter()
+                .find(|m| Some(m.id.as_str()) == fallback_monitor)
+        })
+        .or_else(|| monitors.iter().find(|m| m.primary))
+        .or_else(|| monitors.first())?;
+
+    let work_area = &monitor.work_area;
+    let width = (placement.position.width * monitor.scale).min(work_area.width);
+    let height = (placement.position.height * monitor.scale).min(work_area.height);
+    let x = (work_area.x + placement.position.x * monitor.scale)
+        .clamp(work_area.x, work_area.x + work_area.width - width);
+    let y = (work_area.y + placement.position.y * monitor.scale)
+        .clamp(work_area.y, work_area.y + work_area.height - height);
+
+    Some(WindowPosition {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Returns how far a point is from a rectangle, 0 if the point is inside it.
+fn distance(area: &WindowPosition, x: f64, y: f64) -> f64 {
+    let dx = (area.x - x).max(x - (area.x + area.width)).max(0.0);
+    let dy = (area.y - y).max(y - (area.y + area.height)).max(0.0);
+    dx.hypot(dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{place, restore};
+    use crate::window::{mock::MockBackend, Monitor, WindowBackend, WindowPosition};
+
+    fn monitor(id: &str, x: f64, width: f64, scale: f64, primary: bool) -> Monitor {
+        Monitor {
+            id: id.to_string(),
+            work_area: WindowPosition {
+                x,
+                y: 0.0,
+                width,
+                height: 1000.0,
+            },
+            scale,
+            primary,
+        }
+    }
+
+    #[test]
+    fn window_returns_to_the_same_monitor() {
+        let backend = MockBackend::new();
+        backend.set_monitors(vec![
+            monitor("laptop", 0.0, 1000.0, 1.0, true),
+            monitor("external", 1000.0, 2000.0, 1.0, false),
+        ]);
+        let monitors = backend.monitors();
+        let position = WindowPosition {
+            x: 1200.0,
+            y: 100.0,
+            width: 800.0,
+            height: 600.0,
+        };
+
+        let placement = place(&position, &monitors).unwrap();
+        assert_eq!(placement.monitor.id, "external");
+        assert_eq!(placement.position.x, 200.0);
+        assert_eq!(restore(&placement, &monitors, None), Some(position));
+    }
+
+    #[test]
+    fn window_of_disconnected_monitor_is_clamped_to_fallback() {
+        let docked = vec![
+            monitor("laptop", 0.0, 1000.0, 1.0, true),
+            monitor("external", 1000.0, 2000.0, 1.0, false),
+        ];
+        let position = WindowPosition {
+            x: 2500.0,
+            y: 100.0,
+            width: 1200.0,
+            height: 600.0,
+        };
+        let placement = place(&position, &docked).unwrap();
+
+        let undocked = vec![
+            monitor("laptop", 0.0, 1000.0, 2.0, true),
+            monitor("projector", 1000.0, 800.0, 1.0, false),
+        ];
+        let restored = restore(&placement, &undocked, Some("projector")).unwrap();
+        assert_eq!(restored.width, 800.0);
+        assert_eq!(restored.x, 1000.0);
+
+        // without a fallback the primary monitor is used, and its scale applies
+        let restored = restore(&placement, &undocked, None).unwrap();
+        assert_eq!(restored.width, 1000.0);
+        assert_eq!(restored.height, 1000.0);
+        assert_eq!(restored.x, 0.0);
+    }
+}
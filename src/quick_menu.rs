@@ -1,65 +1,82 @@
 use std::{
+    cell::{Cell, RefCell},
     cmp::max,
     isize,
+    rc::Rc,
     sync::{mpsc::Sender, Arc, Mutex},
 };
 
 use fltk::{
     app::{self, event_key, event_state, event_text},
-    enums::{Align, Color, Event, FrameType},
+    enums::{Align, CallbackTrigger, Color, Event, FrameType, Key},
     frame::Frame,
     group::{Flex, Group},
+    input::Input,
     prelude::*,
     window::Window,
 };
+use mki::Keyboard;
 use serde::{Deserialize, Serialize};
-use windows::Win32::{
-    Foundation::HWND,
-    System::Threading::{AttachThreadInput, GetCurrentThreadId},
-    UI::{
-        Input::KeyboardAndMouse::SetActiveWindow,
-        WindowsAndMessaging::{
-            BringWindowToTop, GetForegroundWindow, GetWindowThreadProcessId, SetForegroundWindow,
-        },
-    },
-};
 
 use crate::{
     config::{Config, QuickMenuAction, QuickMenuConfig, StoredQuickMenuConfig},
     harpoon::HarpoonEvent,
-    window::ApplicationWindow,
+    keyboard::{self, hotkey_string},
+    register::Registers,
+    window::{ApplicationWindow, RestorePolicy, WindowBackend, WindowStatus},
 };
 
-pub struct QuickMenu {
+pub struct QuickMenu<B: WindowBackend> {
+    backend: Arc<B>,
     app: app::App,
     quick_menu_window: Window,
+    banner: Frame,
     window_list: Flex,
+    footer: Frame,
+    /// edits the label of the selected entry, shown over it while renaming
+    rename_input: Input,
+    renaming: Rc<Cell<bool>>,
+    /// set while the next key combination becomes the hotkey of the selected entry
+    assigning_hotkey: Rc<Cell<bool>>,
+    /// the key combination that was pressed while assigning a hotkey, `None` takes the
+    /// hotkey away
+    captured_hotkey: Rc<RefCell<Option<Vec<Keyboard>>>>,
     event_sender: Arc<Mutex<Sender<HarpoonEvent>>>,
     config: Config,
     state: QuickMenuState,
     qm_config: QuickMenuConfig,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MoveCursor {
     ToWindow(isize),
     By(isize),
+    To(isize),
 }
 
 pub struct QuickMenuState {
     pub open: bool,
     pub cursor: isize,
-    pub windows: Vec<ApplicationWindow>,
+    pub windows: Vec<Option<ApplicationWindow>>,
     pub active_window: Option<isize>,
     pub disable_inhibit: bool,
+    pub list_name: String,
+    /// feedback about the last action, shown in the footer until the menu is hidden
+    pub message: Option<String>,
+    /// the register the next cut, yank or paste uses instead of the clipboard
+    pub register: Option<char>,
+    /// the window that had the focus before the menu took it
+    pub focused_before_opening: Option<ApplicationWindow>,
 }
 
 /// QuickMenuStateUpdate is used to update the state of the quick menu
 #[derive(Debug, Clone)]
 pub struct QuickMenuStateUpdate<'a> {
-    pub windows: Option<&'a Vec<ApplicationWindow>>,
+    pub windows: Option<&'a Vec<Option<ApplicationWindow>>>,
     pub move_cursor: Option<MoveCursor>,
     pub disable_inhibit: Option<bool>,
+    pub list_name: Option<&'a str>,
+    pub message: Option<&'a str>,
 }
 
 impl<'a> QuickMenuStateUpdate<'a> {
@@ -68,6 +85,8 @@ impl<'a> QuickMenuStateUpdate<'a> {
             windows: None,
             move_cursor: None,
             disable_inhibit: None,
+            list_name: None,
+            message: None,
         }
     }
 
@@ -79,12 +98,32 @@ impl<'a> QuickMenuStateUpdate<'a> {
         self
     }
 
-    /// Set the windows to the given windows
-    pub fn with_windows(&'a mut self, windows: &'a Vec<ApplicationWindow>) -> &'a mut Self {
+    /// Set the windows to the given slots, `None` is an empty slot
+    pub fn with_windows(&'a mut self, windows: &'a Vec<Option<ApplicationWindow>>) -> &'a mut Self {
         self.windows = Some(windows);
         self
     }
 
+    /// Move the cursor to the given index
+    ///
+    /// If the cursor is out of bounds, it will be clamped to the bounds
+    pub fn with_cursor(&'a mut self, cursor: isize) -> &'a mut Self {
+        self.move_cursor = Some(MoveCursor::To(cursor));
+        self
+    }
+
+    /// Show a message in the footer, e.g. to explain why an action did nothing
+    pub fn with_message(&'a mut self, message: &'a str) -> &'a mut Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Set the name of the list that is shown
+    pub fn with_list_name(&'a mut self, list_name: &'a str) -> &'a mut Self {
+        self.list_name = Some(list_name);
+        self
+    }
+
     /// Set the active window to the window with the given handle
     ///
     /// If the window with the given handle is not found, the active window will not be changed
@@ -93,6 +132,12 @@ impl<'a> QuickMenuStateUpdate<'a> {
         self
     }
 
+    /// Move the cursor in the given way
+    pub fn with_move_cursor(&'a mut self, move_cursor: MoveCursor) -> &'a mut Self {
+        self.move_cursor = Some(move_cursor);
+        self
+    }
+
     /// Set the indicator for whether or not to disable inhibit key events propagation
     pub fn with_disable_inhibit(&'a mut self, disable_inhibit: bool) -> &'a mut Self {
         self.disable_inhibit = Some(disable_inhibit);
@@ -110,16 +155,47 @@ pub enum QuickMenuEvent {
     Select,
     /// Close the quick menu
     Quit,
-    /// Cut the selected window and put it in the clipboard
+    /// Cut the selected window and put it in the register, or in the clipboard
     Cut,
-    /// Paste the selected window from the clipboard after the selected window
+    /// Copy the selected window to the register, or to the clipboard
+    Yank,
+    /// Remove the selected window without putting it in the clipboard
+    Delete,
+    /// Paste the windows in the register, or in the clipboard, after the selected window
     PasteDown,
-    /// Paste the selected window from the clipboard before the selected window
+    /// Paste the windows in the register, or in the clipboard, before the selected window
     PasteUp,
+    /// Use the named register for the next cut, yank or paste, typed as `"` followed by the
+    /// name of the register
+    SelectRegister(char),
     /// Swap the selected window with the window above it
     SwapUp,
     /// Swap the selected window with the window below it
     SwapDown,
+    /// Cycle the restore policy of the selected window
+    CycleRestorePolicy,
+    /// Save the current position, size and state of the selected window
+    RecaptureGeometry,
+    /// Revert the most recent edit to a list
+    Undo,
+    /// Make the most recently undone edit again
+    Redo,
+    /// Add the window that was focused before the quick menu opened to the group of the
+    /// selected window
+    AddToGroup,
+    /// Edit the label of the selected window
+    Rename,
+    /// Use the typed label for the selected window, sent by the rename input
+    ConfirmRename,
+    /// Stop renaming without changing the label
+    CancelRename,
+    /// Use the next key combination as the hotkey of the selected window
+    AssignHotkey,
+    /// Give the selected window the key combination that was pressed, sent by the menu
+    /// while assigning a hotkey
+    ConfirmHotkey,
+    /// Stop assigning a hotkey without changing it
+    CancelHotkey,
 }
 
 impl Into<QuickMenuConfig> for StoredQuickMenuConfig {
@@ -137,21 +213,37 @@ impl Into<QuickMenuConfig> for StoredQuickMenuConfig {
     }
 }
 
-impl QuickMenu {
-    pub fn new(event_sender: Arc<Mutex<Sender<HarpoonEvent>>>, config: Config) -> Self {
-        let app = QuickMenu::create_app();
-        let (quick_menu_window, window_list) = QuickMenu::create_window(&config);
+impl<B: WindowBackend> QuickMenu<B> {
+    pub fn new(
+        backend: Arc<B>,
+        event_sender: Arc<Mutex<Sender<HarpoonEvent>>>,
+        config: Config,
+    ) -> Self {
+        let app = Self::create_app();
+        let (quick_menu_window, banner, window_list, footer, rename_input) =
+            Self::create_window(&config);
         let qm_config = config.quick_menu_config.clone().into();
         let mut quick_menu = QuickMenu {
+            backend,
             app,
             quick_menu_window,
+            banner,
             window_list,
+            footer,
+            rename_input,
+            renaming: Rc::new(Cell::new(false)),
+            assigning_hotkey: Rc::new(Cell::new(false)),
+            captured_hotkey: Rc::new(RefCell::new(None)),
             state: QuickMenuState {
                 open: false,
                 cursor: 0,
                 windows: vec![],
                 active_window: None,
                 disable_inhibit: false,
+                list_name: String::new(),
+                message: None,
+                register: None,
+                focused_before_opening: None,
             },
             event_sender,
             config,
@@ -169,7 +261,7 @@ impl QuickMenu {
         app
     }
 
-    fn create_window(config: &Config) -> (Window, Flex) {
+    fn create_window(config: &Config) -> (Window, Frame, Flex, Frame, Input) {
         let (screen_w, screen_h) = app::screen_size();
         let width = 600;
         let height = 400;
@@ -221,25 +313,44 @@ impl QuickMenu {
         footer.set_label_size(12);
         footer.set_label_color(Color::from_rgb(226, 232, 240));
 
-        if let Some(quit_shortcut_string) =
-            config.get_action_shortcut_string(&HarpoonEvent::QuickMenuEvent(QuickMenuEvent::Quit))
-        {
-            footer.set_label(&format!("Press {} to quit", quit_shortcut_string));
-        } else {
-            footer.set_label("Press Q to quit");
-        }
+        footer.set_label(&footer_label(config));
 
         window.add(&footer);
+
+        let mut rename_input = Input::default().with_size(width, ITEM_HEIGHT);
+        rename_input.set_frame(FrameType::FlatBox);
+        rename_input.set_color(Color::from_rgb(51, 56, 85));
+        rename_input.set_text_color(Color::from_rgb(248, 250, 252));
+        rename_input.set_selection_color(Color::from_rgb(71, 85, 105));
+        rename_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+        rename_input.hide();
+
+        window.add(&rename_input);
         window.end();
 
-        (window, window_list)
+        (window, banner, window_list, footer, rename_input)
     }
 
     fn register_window_event_handlers(&mut self) {
         let event_sender = Arc::clone(&self.event_sender);
         let actions = self.qm_config.actions.clone();
+        // whether a `"` was typed, so the next letter names a register
+        let mut awaiting_register = false;
+        let renaming = Rc::clone(&self.renaming);
+        let assigning_hotkey = Rc::clone(&self.assigning_hotkey);
+        let captured_hotkey = Rc::clone(&self.captured_hotkey);
+
+        let rename_sender = Arc::clone(&self.event_sender);
+        self.rename_input.set_callback(move |_| {
+            if let Ok(event_sender) = rename_sender.lock() {
+                _ = event_sender.send(HarpoonEvent::QuickMenuEvent(QuickMenuEvent::ConfirmRename));
+            }
+        });
 
         self.quick_menu_window.handle(move |_, ev| match ev {
+            // moving the focus between the menu and the rename input unfocuses the menu too
+            Event::Unfocus if renaming.get() && app::focus().is_some() => false,
+
             Event::Unfocus => {
                 match event_sender.lock() {
                     Ok(sender) => {
@@ -251,24 +362,91 @@ impl QuickMenu {
                 true
             }
 
-            Event::KeyDown => QuickMenu::handle_keydown_event(&event_sender, &actions),
+            // the rename input handles the keys it needs, the others mustn't trigger actions
+            Event::KeyDown if renaming.get() => {
+                if event_key() == Key::Escape {
+                    if let Ok(event_sender) = event_sender.lock() {
+                        _ = event_sender
+                            .send(HarpoonEvent::QuickMenuEvent(QuickMenuEvent::CancelRename));
+                    }
+                }
+                true
+            }
+
+            Event::KeyDown if assigning_hotkey.get() => {
+                let event = match event_key() {
+                    Key::Escape => QuickMenuEvent::CancelHotkey,
+                    Key::BackSpace | Key::Delete => {
+                        *captured_hotkey.borrow_mut() = None;
+                        QuickMenuEvent::ConfirmHotkey
+                    }
+                    key => match keyboard::mki_keys_from_fltk(key, event_state()) {
+                        Some(hotkey) => {
+                            *captured_hotkey.borrow_mut() = Some(hotkey);
+                            QuickMenuEvent::ConfirmHotkey
+                        }
+                        // a modifier on its own, wait for the rest of the combination
+                        None => return true,
+                    },
+                };
+                if let Ok(event_sender) = event_sender.lock() {
+                    _ = event_sender.send(HarpoonEvent::QuickMenuEvent(event));
+                }
+                true
+            }
+
+            Event::KeyDown => {
+                Self::handle_keydown_event(&event_sender, &actions, &mut awaiting_register)
+            }
             _ => false,
         });
     }
 
     /// Hides the quick menu.
     pub fn hide(&mut self) {
+        self.stop_renaming();
+        self.assigning_hotkey.set(false);
         self.quick_menu_window.hide();
         self.state.open = false;
+        self.state.register = None;
+        self.state.focused_before_opening = None;
+
+        if self.state.message.take().is_some() {
+            self.footer.set_label(&footer_label(&self.config));
+        }
     }
 
     fn handle_keydown_event(
         event_sender: &Arc<Mutex<Sender<HarpoonEvent>>>,
         actions: &Vec<QuickMenuAction>,
+        awaiting_register: &mut bool,
     ) -> bool {
         let event_key = event_key();
         let event_state = event_state();
-        let event_text = event_text().to_lowercase();
+        let event_text = event_text();
+
+        // registers are named like in vim, `"a` uses register a for the next action
+        if event_text == "\"" {
+            *awaiting_register = true;
+            return true;
+        }
+        // modifier keys have no text, they don't cancel naming a register
+        if *awaiting_register && !event_text.is_empty() {
+            *awaiting_register = false;
+            let mut chars = event_text.chars();
+            if let (Some(name), None) = (chars.next(), chars.next()) {
+                if Registers::is_register(name) {
+                    if let Ok(event_sender) = event_sender.lock() {
+                        _ = event_sender.send(HarpoonEvent::QuickMenuEvent(
+                            QuickMenuEvent::SelectRegister(name),
+                        ));
+                    }
+                    return true;
+                }
+            }
+        }
+
+        let event_text = event_text.to_lowercase();
 
         let mut handled = false;
 
@@ -299,6 +477,10 @@ impl QuickMenu {
     pub fn show(&mut self) {
         self.render_window_list();
 
+        if !self.state.open {
+            self.state.focused_before_opening = self.backend.current_window();
+        }
+
         let window = &mut self.quick_menu_window;
         self.state.open = true;
         window.show();
@@ -306,43 +488,8 @@ impl QuickMenu {
         let (screen_width, screen_height) = (screen_width as i32, screen_height as i32);
         let (width, height) = (window.width(), window.height());
         window.set_pos(screen_width / 2 - width / 2, screen_height / 2 - height / 2);
-        let hwnd = HWND(window.raw_handle() as isize);
-
-        unsafe {
-            let foreground_window = GetForegroundWindow();
-            if foreground_window.0 == 0 {
-                println!("Failed to get foreground window");
-                return;
-            }
-
-            // get the current foreground thread
-            let foreground_thread = GetWindowThreadProcessId(foreground_window, None);
 
-            if foreground_thread == 0 {
-                println!("Failed to get foreground thread");
-                return;
-            }
-
-            // get the current thread
-            let current_thread = GetCurrentThreadId();
-
-            // attach the current thread to the foreground thread
-            let thread_attached = current_thread == foreground_thread
-                || AttachThreadInput(current_thread, foreground_thread, true).as_bool();
-
-            if !thread_attached {
-                println!("Failed to attach thread");
-                return;
-            }
-
-            SetForegroundWindow(hwnd);
-            BringWindowToTop(hwnd);
-            SetActiveWindow(hwnd);
-
-            if thread_attached {
-                AttachThreadInput(current_thread, foreground_thread, false);
-            }
-        }
+        self.backend.focus_window(window.raw_handle() as isize);
     }
 
     /// Toggle the visibility of the quick menu
@@ -409,51 +556,155 @@ impl QuickMenu {
                 }
             }
             QuickMenuEvent::Cut => {
-                let cursor = self.state.cursor as usize;
-                let event_sender = match self.event_sender.lock() {
-                    Ok(sender) => sender,
-                    Err(_) => return,
-                };
-
-                match event_sender.send(HarpoonEvent::CutWindow(cursor)) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        println!("Error sending event: {}", err);
-                    }
-                }
+                let index = self.state.cursor as usize;
+                let register = self.state.register.take();
+                self.send_event(HarpoonEvent::CutWindow { index, register });
+            }
+            QuickMenuEvent::Yank => {
+                let index = self.state.cursor as usize;
+                let register = self.state.register.take();
+                self.send_event(HarpoonEvent::YankWindow { index, register });
+            }
+            QuickMenuEvent::Delete => {
+                self.send_event(HarpoonEvent::DeleteWindow(self.state.cursor as usize));
             }
             QuickMenuEvent::PasteUp => {
-                let cursor = self.state.cursor as usize;
-                let event_sender = match self.event_sender.lock() {
-                    Ok(sender) => sender,
-                    Err(_) => return,
-                };
-
-                match event_sender.send(HarpoonEvent::PasteWindow(cursor)) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        println!("Error sending event: {}", err);
-                    }
-                }
+                let index = self.state.cursor as usize;
+                let register = self.state.register.take();
+                self.send_event(HarpoonEvent::PasteWindow { index, register });
             }
             QuickMenuEvent::PasteDown => {
-                let cursor = self.state.cursor as usize;
-                let event_sender = match self.event_sender.lock() {
-                    Ok(sender) => sender,
-                    Err(_) => return,
-                };
-
-                match event_sender.send(HarpoonEvent::PasteWindow(cursor + 1)) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        println!("Error sending event: {}", err);
+                let index = self.state.cursor as usize + 1;
+                let register = self.state.register.take();
+                self.send_event(HarpoonEvent::PasteWindow { index, register });
+            }
+            QuickMenuEvent::SelectRegister(name) => {
+                if !Registers::is_register(name) {
+                    return;
+                }
+                self.state.register = Some(name);
+                self.update_state(QuickMenuStateUpdate::new().with_message(&format!("\"{}", name)));
+            }
+            QuickMenuEvent::CycleRestorePolicy => {
+                self.send_event(HarpoonEvent::CycleRestorePolicy(self.state.cursor as usize));
+            }
+            QuickMenuEvent::RecaptureGeometry => {
+                self.send_event(HarpoonEvent::RecaptureGeometryByIndex(
+                    self.state.cursor as usize,
+                ));
+            }
+            QuickMenuEvent::Undo => self.send_event(HarpoonEvent::Undo),
+            QuickMenuEvent::Redo => self.send_event(HarpoonEvent::Redo),
+            QuickMenuEvent::AddToGroup => {
+                self.send_event(HarpoonEvent::AddCurrentWindowToGroup(
+                    self.state.cursor as usize,
+                ));
+            }
+            QuickMenuEvent::Rename => self.start_renaming(),
+            QuickMenuEvent::ConfirmRename => {
+                if !self.renaming.get() {
+                    return;
+                }
+                let label = self.rename_input.value();
+                self.stop_renaming();
+                self.send_event(HarpoonEvent::RenameWindow {
+                    index: self.state.cursor as usize,
+                    label: Some(label),
+                });
+            }
+            QuickMenuEvent::CancelRename => self.stop_renaming(),
+            QuickMenuEvent::AssignHotkey => self.start_assigning_hotkey(),
+            QuickMenuEvent::ConfirmHotkey => {
+                if !self.assigning_hotkey.get() {
+                    return;
+                }
+                let hotkey = self.captured_hotkey.borrow_mut().take();
+                // a global hotkey without a modifier would swallow the key everywhere
+                if let Some([key]) = hotkey.as_deref() {
+                    if !is_function_key(*key) {
+                        self.update_state(QuickMenuStateUpdate::new().with_message(
+                            "A hotkey needs a modifier like Ctrl or Alt, or a function key",
+                        ));
+                        return;
                     }
                 }
+                self.stop_assigning_hotkey();
+                self.send_event(HarpoonEvent::AssignHotkey {
+                    index: self.state.cursor as usize,
+                    hotkey,
+                });
             }
+            QuickMenuEvent::CancelHotkey => self.stop_assigning_hotkey(),
             _ => {}
         }
     }
 
+    /// Shows the rename input over the selected entry, filled with its label or title.
+    fn start_renaming(&mut self) {
+        let window = match self.state.windows.get(self.state.cursor as usize) {
+            Some(Some(window)) => window,
+            _ => return,
+        };
+        let value = window.label.clone().unwrap_or_else(|| window.title.clone());
+
+        let y = self.window_list.y() + self.state.cursor as i32 * ITEM_HEIGHT;
+        let (x, width) = (self.window_list.x(), self.window_list.width());
+        self.rename_input.resize(x, y, width, ITEM_HEIGHT);
+        self.rename_input.set_value(&value);
+        // select everything, so typing replaces the old label
+        _ = self.rename_input.set_position(value.len() as i32);
+        _ = self.rename_input.set_mark(0);
+
+        self.renaming.set(true);
+        self.rename_input.show();
+        _ = self.rename_input.take_focus();
+        self.app.redraw();
+    }
+
+    /// Hides the rename input and gives the focus back to the menu.
+    fn stop_renaming(&mut self) {
+        if !self.renaming.get() {
+            return;
+        }
+        app::set_focus(&self.quick_menu_window);
+        self.rename_input.hide();
+        self.renaming.set(false);
+        self.app.redraw();
+    }
+
+    /// Waits for the key combination that becomes the hotkey of the selected entry, and says
+    /// so in the footer.
+    fn start_assigning_hotkey(&mut self) {
+        let window = match self.state.windows.get(self.state.cursor as usize) {
+            Some(Some(window)) => window,
+            _ => return,
+        };
+        let message = format!(
+            "Press the hotkey for {}, Backspace removes it, Esc cancels",
+            window.label.as_ref().unwrap_or(&window.title)
+        );
+        self.assigning_hotkey.set(true);
+        self.update_state(QuickMenuStateUpdate::new().with_message(&message));
+    }
+
+    fn stop_assigning_hotkey(&mut self) {
+        self.assigning_hotkey.set(false);
+        self.state.message = None;
+        self.footer.set_label(&footer_label(&self.config));
+        self.app.redraw();
+    }
+
+    fn send_event(&self, event: HarpoonEvent) {
+        let event_sender = match self.event_sender.lock() {
+            Ok(sender) => sender,
+            Err(_) => return,
+        };
+
+        if let Err(err) = event_sender.send(event) {
+            println!("Error sending event: {}", err);
+        }
+    }
+
     pub fn render_window_list(&mut self) {
         let window_list = &mut self.window_list;
 
@@ -462,7 +713,7 @@ impl QuickMenu {
         let cursor_pos = self.state.cursor;
 
         window_list.clear();
-        let item_height: i32 = 30;
+        let item_height = ITEM_HEIGHT;
         let x = window_list.x();
         let y = match windows.len() as i32 * item_height > 200 {
             true => 50 - (max(cursor_pos as i32 - 2, 0) * item_height),
@@ -476,11 +727,61 @@ impl QuickMenu {
         window_list.resize(x, y, width, height);
 
         for (index, window) in windows.iter().enumerate() {
+            let window = match window {
+                Some(window) => window,
+                None => {
+                    let mut item = Frame::default()
+                        .size_of_parent()
+                        .with_label(&format!("{}: empty", index + 1));
+                    item.set_align(Align::Left | Align::Inside);
+                    item.set_frame(FrameType::FlatBox);
+                    item.set_color(Color::from_rgb(31, 41, 59));
+                    item.set_label_color(Color::from_rgb(71, 85, 105));
+
+                    if index == cursor_pos as usize {
+                        item.set_color(Color::from_rgb(51, 56, 85));
+                        item.set_label_color(Color::from_rgb(148, 163, 184));
+                    }
+                    window_list.add(&item);
+                    continue;
+                }
+            };
+            let status_tag = match window.status {
+                WindowStatus::Alive => "",
+                WindowStatus::Closed => " [closed]",
+                WindowStatus::Rebound => " [relaunched]",
+                WindowStatus::Unresponsive => " [not responding]",
+            };
+            let policy_tag = match window.restore_policy {
+                None => "",
+                Some(RestorePolicy::Geometry) => " (geometry)",
+                Some(RestorePolicy::StateOnly) => " (state only)",
+                Some(RestorePolicy::FocusOnly) => " (focus only)",
+            };
+            let group_tag = match window.group_members.is_empty() {
+                true => String::new(),
+                false => {
+                    let members: Vec<&str> = window
+                        .group_members
+                        .iter()
+                        .map(|member| member.process_name.as_str())
+                        .collect();
+                    format!(" + {}", members.join(", "))
+                }
+            };
+            let hotkey_tag = match &window.hotkey {
+                Some(hotkey) => format!(" <{}>", hotkey_string(hotkey)),
+                None => String::new(),
+            };
             let label = format!(
-                "{}: {}: \"{}\"",
+                "{}: {}: \"{}\"{}{}{}{}",
                 index + 1,
                 window.process_name,
-                window.title,
+                window.label.as_ref().unwrap_or(&window.title),
+                group_tag,
+                hotkey_tag,
+                status_tag,
+                policy_tag,
             );
             let mut item = Frame::default().size_of_parent().with_label(&label);
             item.set_align(Align::Left | Align::Inside);
@@ -488,11 +789,11 @@ impl QuickMenu {
             item.set_frame(FrameType::FlatBox);
 
             item.set_color(Color::from_rgb(31, 41, 59));
-            item.set_label_color(Color::from_rgb(226, 232, 240));
+            item.set_label_color(status_color(window.status, false));
 
             if index == cursor_pos as usize {
                 item.set_color(Color::from_rgb(51, 56, 85));
-                item.set_label_color(Color::from_rgb(248, 250, 252));
+                item.set_label_color(status_color(window.status, true));
             }
             window_list.add(&item);
         }
@@ -546,24 +847,38 @@ impl QuickMenu {
             updated = true;
         }
 
+        if let Some(message) = state.message {
+            self.state.message = Some(message.to_string());
+            self.footer.set_label(message);
+            updated = true;
+        }
+
+        if let Some(list_name) = state.list_name {
+            self.state.list_name = list_name.to_string();
+            self.banner
+                .set_label(&format!("Harpoon - {}", self.state.list_name));
+            updated = true;
+        }
+
         if let Some(ref move_cursor) = state.move_cursor {
             match move_cursor {
                 MoveCursor::ToWindow(id) => {
-                    if let Some(index) = self.state.windows.iter().position(|w| w.window_id == *id)
+                    if let Some(index) = self
+                        .state
+                        .windows
+                        .iter()
+                        .position(|w| matches!(w, Some(w) if w.window_id == *id))
                     {
                         self.state.cursor = index as isize;
                     }
                 }
 
                 MoveCursor::By(delta) => {
-                    let new_cursor = self.state.cursor + delta;
-                    let max = self.state.windows.len() as isize - 1;
-                    let cursor = match new_cursor {
-                        isize::MIN..=0 => 0,
-                        i if i <= max => new_cursor,
-                        _ => max,
-                    };
-                    self.state.cursor = cursor;
+                    self.state.cursor = self.clamp_cursor(self.state.cursor + delta);
+                }
+
+                MoveCursor::To(cursor) => {
+                    self.state.cursor = self.clamp_cursor(*cursor);
                 }
             }
             updated = true;
@@ -574,6 +889,29 @@ impl QuickMenu {
         }
     }
 
+    pub fn is_open(&self) -> bool {
+        self.state.open
+    }
+
+    /// Returns the window that was focused when the menu was opened.
+    pub fn focused_before_opening(&self) -> Option<&ApplicationWindow> {
+        self.state.focused_before_opening.as_ref()
+    }
+
+    /// Returns the position of the cursor in the window list
+    pub fn cursor(&self) -> isize {
+        self.state.cursor
+    }
+
+    fn clamp_cursor(&self, cursor: isize) -> isize {
+        let max = self.state.windows.len() as isize - 1;
+        match cursor {
+            isize::MIN..=0 => 0,
+            i if i <= max => cursor,
+            _ => max,
+        }
+    }
+
     /// is called when internal state is updated
     fn notify_updated(&mut self) {
         if self.state.open {
@@ -583,3 +921,46 @@ impl QuickMenu {
         // handle updates that need to be handled regardless of the menu state
     }
 }
+
+/// The height of an entry in the window list.
+const ITEM_HEIGHT: i32 = 30;
+
+/// The hint shown in the footer when there is no message.
+fn footer_label(config: &Config) -> String {
+    match config.get_action_shortcut_string(&HarpoonEvent::QuickMenuEvent(QuickMenuEvent::Quit)) {
+        Some(quit_shortcut_string) => format!("Press {} to quit", quit_shortcut_string),
+        None => "Press Q to quit".to_string(),
+    }
+}
+
+fn is_function_key(key: Keyboard) -> bool {
+    matches!(
+        key,
+        Keyboard::F1
+            | Keyboard::F2
+            | Keyboard::F3
+            | Keyboard::F4
+            | Keyboard::F5
+            | Keyboard::F6
+            | Keyboard::F7
+            | Keyboard::F8
+            | Keyboard::F9
+            | Keyboard::F10
+            | Keyboard::F11
+            | Keyboard::F12
+    )
+}
+
+/// The label color of an entry with the given status.
+fn status_color(status: WindowStatus, selected: bool) -> Color {
+    match (status, selected) {
+        (WindowStatus::Alive, false) => Color::from_rgb(226, 232, 240),
+        (WindowStatus::Alive, true) => Color::from_rgb(248, 250, 252),
+        (WindowStatus::Closed, false) => Color::from_rgb(100, 116, 139),
+        (WindowStatus::Closed, true) => Color::from_rgb(148, 163, 184),
+        (WindowStatus::Rebound, false) => Color::from_rgb(134, 239, 172),
+        (WindowStatus::Rebound, true) => Color::from_rgb(187, 247, 208),
+        (WindowStatus::Unresponsive, false) => Color::from_rgb(251, 191, 36),
+        (WindowStatus::Unresponsive, true) => Color::from_rgb(252, 211, 77),
+    }
+}
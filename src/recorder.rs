@@ -0,0 +1,91 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{harpoon::HarpoonEvent, state::PersistedState, window::ApplicationWindow};
+
+/// A line of a recording.
+///
+/// Every time harpoon starts recording it writes the lists it starts out with,
+/// followed by a line for every event it handles. Timestamps are milliseconds
+/// since the unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordedEntry {
+    /// The lists at the moment recording started
+    Start {
+        timestamp: u64,
+        start: PersistedState,
+    },
+    /// An event, together with the window that was focused when it was handled
+    Event {
+        timestamp: u64,
+        focused: Option<Box<ApplicationWindow>>,
+        event: HarpoonEvent,
+    },
+}
+
+/// Appends the events harpoon handles to a JSONL file, so a session can be replayed later.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    /// Opens the recording at the given path, creating it if it doesn't exist yet, and
+    /// records the lists harpoon starts out with.
+    pub fn open(path: &str, start: PersistedState) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut recorder = Self { file };
+        recorder.write(&RecordedEntry::Start {
+            timestamp: timestamp(),
+            start,
+        })?;
+        Ok(recorder)
+    }
+
+    pub fn record(
+        &mut self,
+        event: &HarpoonEvent,
+        focused: Option<ApplicationWindow>,
+    ) -> Result<()> {
+        self.write(&RecordedEntry::Event {
+            timestamp: timestamp(),
+            focused: focused.map(Box::new),
+            event: event.clone(),
+        })
+    }
+
+    fn write(&mut self, entry: &RecordedEntry) -> Result<()> {
+        let line = serde_json::to_string(entry)?;
+        writeln!(self.file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// Reads all entries of a recording, skipping empty lines.
+pub fn load_recording(path: &str) -> Result<Vec<RecordedEntry>> {
+    let file = File::open(path)?;
+    let mut entries = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|err| anyhow!("line {} of {}: {}", index + 1, path, err))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
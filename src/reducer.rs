@@ -0,0 +1,1095 @@
+use mki::Keyboard;
+
+use crate::{
+    harpoon::HarpoonEvent,
+    history::FocusHistory,
+    keyboard::hotkey_string,
+    list::HarpoonList,
+    quick_menu::MoveCursor,
+    register::Registers,
+    state::PersistedState,
+    undo::UndoHistory,
+    window::{ApplicationWindow, RestorePolicy, WindowStatus},
+};
+
+/// The window lists and everything that is needed to change them.
+///
+/// The state doesn't talk to the window backend or the quick menu, it is only
+/// changed by [`reduce`]. Whatever has to happen outside of it is returned as
+/// an [`Effect`] for `Harpoon` to carry out.
+#[derive(Debug)]
+pub struct State {
+    /// the named window lists, there is always at least one
+    pub lists: Vec<HarpoonList>,
+    /// the index of the list that the quick menu and slot hotkeys act on
+    pub active_list: usize,
+    /// the harpooned windows that were focused, used to navigate back and forward
+    pub history: FocusHistory,
+    /// the edits made to the lists, used to undo and redo them
+    pub edits: UndoHistory,
+    /// the named registers, the unnamed register is the clipboard of each list
+    pub registers: Registers,
+    /// the focused window, kept up to date by `Harpoon` before every event
+    pub focused: Option<ApplicationWindow>,
+}
+
+impl State {
+    pub fn new(lists: Vec<HarpoonList>, active_list: usize) -> Self {
+        Self {
+            lists,
+            active_list,
+            history: FocusHistory::new(),
+            edits: UndoHistory::new(),
+            registers: Registers::new(),
+            focused: None,
+        }
+    }
+
+    pub fn active_list(&self) -> &HarpoonList {
+        &self.lists[self.active_list]
+    }
+
+    /// Returns the part of the state that is kept across restarts.
+    pub fn to_persisted(&self) -> PersistedState {
+        PersistedState {
+            lists: self.lists.clone(),
+            active_list: self.active_list,
+            windows: vec![],
+        }
+    }
+}
+
+/// Something that has to happen outside of the state after an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Focus the window and restore its saved position, size and state
+    Navigate(Box<ApplicationWindow>),
+    /// Show the windows and name of the active list in the quick menu and move its cursor
+    ShowList(MoveCursor),
+    /// Tell the user what an event did if that isn't obvious
+    ShowMessage(String),
+    /// Tell the user why an event did nothing
+    Refuse(String),
+    /// Write the lists to disk
+    SaveState,
+}
+
+/// Applies an event to the state and returns the new state together with what has to happen
+/// outside of it. Events that don't change the lists are ignored.
+pub fn reduce(mut state: State, event: &HarpoonEvent) -> (State, Vec<Effect>) {
+    let effects = match event {
+        HarpoonEvent::AddCurrentApplicationWindow => add_current_window(&mut state),
+        HarpoonEvent::AddCurrentWindowToSlot(slot) => {
+            add_current_window_to_slot(&mut state, *slot, false)
+        }
+        HarpoonEvent::ReplaceSlotWithCurrentWindow(slot) => {
+            add_current_window_to_slot(&mut state, *slot, true)
+        }
+        HarpoonEvent::RemoveCurrentApplicationWindow => remove_current_window(&mut state),
+        HarpoonEvent::ClearSlot(slot) => delete_window(&mut state, *slot).1,
+        HarpoonEvent::AddCurrentWindowToGroup(index) => {
+            add_current_window_to_group(&mut state, *index)
+        }
+        HarpoonEvent::RebindToCurrentWindow => rebind_to_current_window(&mut state),
+
+        HarpoonEvent::NavigateToNextWindow => navigate_relative(&state, 1),
+        HarpoonEvent::NavigateToPreviousWindow => navigate_relative(&state, -1),
+        HarpoonEvent::NavigateToWindowByIndex(slot) => navigate_to_slot(&state, *slot),
+        HarpoonEvent::NavigateToWindowByHotkey(hotkey) => navigate_to_hotkey(&mut state, hotkey),
+
+        HarpoonEvent::SwapWindows { from, to } => swap_windows(&mut state, *from, *to),
+        HarpoonEvent::CutWindow { index, register } => {
+            let (window, effects) = delete_window(&mut state, *index);
+            if let Some(window) = window {
+                store_in_register(&mut state, *register, vec![window]);
+            }
+            effects
+        }
+        HarpoonEvent::YankWindow { index, register } => {
+            if let Some(window) = state.active_list().get(*index).cloned() {
+                store_in_register(&mut state, *register, vec![window]);
+            }
+            vec![]
+        }
+        HarpoonEvent::DeleteWindow(index) => delete_window(&mut state, *index).1,
+        HarpoonEvent::PasteWindow { index, register } => {
+            paste_windows(&mut state, *index, *register)
+        }
+        HarpoonEvent::CycleRestorePolicy(index) => cycle_restore_policy(&mut state, *index),
+        HarpoonEvent::RenameWindow { index, label } => rename_window(&mut state, *index, label),
+        HarpoonEvent::AssignHotkey { index, hotkey } => assign_hotkey(&mut state, *index, hotkey),
+
+        HarpoonEvent::Undo => match state.edits.undo(&mut state.lists) {
+            Some(index) => show_edited_list(&mut state, index),
+            None => refuse("Nothing to undo"),
+        },
+        HarpoonEvent::Redo => match state.edits.redo(&mut state.lists) {
+            Some(index) => show_edited_list(&mut state, index),
+            None => refuse("Nothing to redo"),
+        },
+
+        HarpoonEvent::SwitchToList(name) => switch_to_list(&mut state, name),
+        HarpoonEvent::SwitchToNextList => switch_list_relative(&mut state, 1),
+        HarpoonEvent::SwitchToPreviousList => switch_list_relative(&mut state, -1),
+        HarpoonEvent::CreateList(name) => create_list(&mut state, name),
+        HarpoonEvent::DeleteList(name) => delete_list(&mut state, name),
+
+        _ => vec![],
+    };
+    (state, effects)
+}
+
+/// Returns whether the event is handled by [`reduce`], the other events are carried out by
+/// `Harpoon` itself.
+pub fn handles(event: &HarpoonEvent) -> bool {
+    matches!(
+        event,
+        HarpoonEvent::AddCurrentApplicationWindow
+            | HarpoonEvent::AddCurrentWindowToSlot(_)
+            | HarpoonEvent::ReplaceSlotWithCurrentWindow(_)
+            | HarpoonEvent::RemoveCurrentApplicationWindow
+            | HarpoonEvent::ClearSlot(_)
+            | HarpoonEvent::AddCurrentWindowToGroup(_)
+            | HarpoonEvent::RebindToCurrentWindow
+            | HarpoonEvent::NavigateToNextWindow
+            | HarpoonEvent::NavigateToPreviousWindow
+            | HarpoonEvent::NavigateToWindowByIndex(_)
+            | HarpoonEvent::NavigateToWindowByHotkey(_)
+            | HarpoonEvent::SwapWindows { .. }
+            | HarpoonEvent::CutWindow { .. }
+            | HarpoonEvent::YankWindow { .. }
+            | HarpoonEvent::DeleteWindow(_)
+            | HarpoonEvent::PasteWindow { .. }
+            | HarpoonEvent::CycleRestorePolicy(_)
+            | HarpoonEvent::RenameWindow { .. }
+            | HarpoonEvent::AssignHotkey { .. }
+            | HarpoonEvent::Undo
+            | HarpoonEvent::Redo
+            | HarpoonEvent::SwitchToList(_)
+            | HarpoonEvent::SwitchToNextList
+            | HarpoonEvent::SwitchToPreviousList
+            | HarpoonEvent::CreateList(_)
+            | HarpoonEvent::DeleteList(_)
+    )
+}
+
+fn refuse(message: &str) -> Vec<Effect> {
+    vec![Effect::Refuse(message.to_string())]
+}
+
+/// Adds the focused window to the first empty slot, or updates its entry if it is already
+/// harpooned.
+fn add_current_window(state: &mut State) -> Vec<Effect> {
+    let mut window = match state.focused.clone() {
+        Some(window) => window,
+        None => return refuse("No window found"),
+    };
+    let list = &mut state.lists[state.active_list];
+    let hwnd = window.window_id;
+    let slot = list.position(hwnd);
+
+    if let Some(entry) = slot.and_then(|slot| list.get(slot)) {
+        keep_entry_settings(&mut window, entry.clone());
+        if *entry == window {
+            return vec![];
+        }
+    }
+    state.edits.record(list);
+
+    let slot = slot.unwrap_or_else(|| list.first_empty_slot());
+    list.set(slot, Some(window));
+    vec![
+        Effect::ShowList(MoveCursor::ToWindow(hwnd)),
+        Effect::SaveState,
+    ]
+}
+
+/// Carries over what was set on an entry rather than read from its window, when the entry
+/// is replaced with a fresh copy of the window.
+fn keep_entry_settings(window: &mut ApplicationWindow, entry: ApplicationWindow) {
+    window.label = entry.label;
+    window.hotkey = entry.hotkey;
+    window.group_members = entry.group_members;
+    window.restore_policy = entry.restore_policy;
+    window.status = entry.status;
+}
+
+/// Pins the focused window to a slot. A slot that is taken by another window is only
+/// changed if `replace` is set.
+fn add_current_window_to_slot(state: &mut State, slot: usize, replace: bool) -> Vec<Effect> {
+    let mut window = match state.focused.clone() {
+        Some(window) => window,
+        None => return refuse("No window found"),
+    };
+    let hwnd = window.window_id;
+    let list = &mut state.lists[state.active_list];
+
+    let replaced = list.get(slot).filter(|w| w.window_id != hwnd).cloned();
+    if let (Some(taken), false) = (&replaced, replace) {
+        return refuse(&format!("Slot {} is taken by {}", slot + 1, taken.title));
+    }
+
+    state.edits.record(list);
+    // a window is only pinned to one slot, so move it if it's already in another one
+    if let Some(previous) = list.position(hwnd) {
+        if let Some(entry) = list.set(previous, None) {
+            keep_entry_settings(&mut window, entry);
+        }
+    }
+    let title = window.title.clone();
+    list.set(slot, Some(window));
+
+    let mut effects = vec![
+        Effect::ShowList(MoveCursor::ToWindow(hwnd)),
+        Effect::SaveState,
+    ];
+    if let Some(replaced) = replaced {
+        effects.push(Effect::ShowMessage(format!(
+            "Replaced {} in slot {} with {}",
+            replaced.title,
+            slot + 1,
+            title
+        )));
+    }
+    effects
+}
+
+/// Adds the focused window to the group of the entry at the given index, above the windows
+/// that are already in it.
+fn add_current_window_to_group(state: &mut State, index: usize) -> Vec<Effect> {
+    let window = match state.focused.clone() {
+        Some(window) => window,
+        None => return refuse("No window found"),
+    };
+    let hwnd = window.window_id;
+    let list = &mut state.lists[state.active_list];
+
+    let entry = match list.get(index) {
+        Some(entry) => entry,
+        None => return refuse(&format!("Slot {} is empty", index + 1)),
+    };
+    if entry.window_id == hwnd || entry.group_members.iter().any(|m| m.window_id == hwnd) {
+        return refuse(&format!(
+            "{} is already in slot {}",
+            window.title,
+            index + 1
+        ));
+    }
+    let primary = entry.title.clone();
+
+    state.edits.record(list);
+    let title = window.title.clone();
+    if let Some(entry) = list.get_mut(index) {
+        entry.group_members.push(window);
+    }
+    vec![
+        Effect::ShowList(MoveCursor::By(0)),
+        Effect::SaveState,
+        Effect::ShowMessage(format!("Grouped {} with {}", title, primary)),
+    ]
+}
+
+/// Binds the entry of the focused window's program to the focused window, keeping the rest
+/// of the entry. If the program has several entries, the one that was navigated to most
+/// recently is rebound, or else the first one.
+fn rebind_to_current_window(state: &mut State) -> Vec<Effect> {
+    let window = match state.focused.clone() {
+        Some(window) => window,
+        None => return refuse("No window found"),
+    };
+    let current = state.history.current();
+    let list = &mut state.lists[state.active_list];
+    if list.position(window.window_id).is_some() {
+        return refuse(&format!("{} is already harpooned", window.title));
+    }
+
+    let same_program = |slot: &Option<ApplicationWindow>| matches!(slot, Some(w) if w.process_path == window.process_path);
+    let slot = list
+        .slots
+        .iter()
+        .position(|slot| same_program(slot) && slot.as_ref().map(|w| w.window_id) == current)
+        .or_else(|| list.slots.iter().position(same_program));
+    let slot = match slot {
+        Some(slot) => slot,
+        None => return refuse(&format!("{} isn't harpooned", window.process_name)),
+    };
+
+    let hwnd = window.window_id;
+    if let Some(old_id) = list.get(slot).map(|entry| entry.window_id) {
+        // like a relaunched window, the new one takes the place of the old one everywhere,
+        // except in the edit recorded below so undoing the rebind brings the old one back
+        state.history.replace(old_id, hwnd);
+        state.edits.replace(old_id, hwnd);
+        state.registers.replace(old_id, hwnd);
+    }
+    state.edits.record(list);
+    let title = window.title.clone();
+    if let Some(entry) = list.get_mut(slot) {
+        entry.window_id = window.window_id;
+        entry.title = window.title;
+        entry.position = window.position;
+        entry.state = window.state;
+        entry.placement = window.placement;
+        entry.status = WindowStatus::Alive;
+    }
+    vec![
+        Effect::ShowList(MoveCursor::ToWindow(hwnd)),
+        Effect::SaveState,
+        Effect::ShowMessage(format!("Slot {} is now bound to {}", slot + 1, title)),
+    ]
+}
+
+fn remove_current_window(state: &mut State) -> Vec<Effect> {
+    let window = match state.focused.clone() {
+        Some(window) => window,
+        None => return refuse("No window found"),
+    };
+    match state.active_list().position(window.window_id) {
+        Some(slot) => {
+            let (_, mut effects) = delete_window(state, slot);
+            effects.push(Effect::ShowMessage(format!(
+                "Removed {} from slot {}",
+                window.title,
+                slot + 1
+            )));
+            effects
+        }
+        None => refuse(&format!("{} isn't harpooned", window.title)),
+    }
+}
+
+fn navigate_to_slot(state: &State, slot: usize) -> Vec<Effect> {
+    match state.active_list().get(slot) {
+        Some(window) => vec![Effect::Navigate(Box::new(window.clone()))],
+        None => refuse(&format!("Slot {} is empty", slot + 1)),
+    }
+}
+
+/// Navigate to the entry with the hotkey, switching to the list it is in if it isn't in the
+/// active list.
+fn navigate_to_hotkey(state: &mut State, hotkey: &[Keyboard]) -> Vec<Effect> {
+    let with_hotkey = |list: &HarpoonList| {
+        list.windows()
+            .find(|w| w.hotkey.as_deref() == Some(hotkey))
+            .cloned()
+    };
+    if let Some(window) = with_hotkey(state.active_list()) {
+        return vec![Effect::Navigate(Box::new(window))];
+    }
+
+    let found = state
+        .lists
+        .iter()
+        .enumerate()
+        .find_map(|(index, list)| Some((index, with_hotkey(list)?)));
+    match found {
+        Some((index, window)) => {
+            let mut effects = switch_to_list_index(state, index);
+            effects.push(Effect::Navigate(Box::new(window)));
+            effects
+        }
+        None => vec![],
+    }
+}
+
+/// Navigate `delta` windows away from the current window, wrapping around the list and
+/// skipping empty slots.
+fn navigate_relative(state: &State, delta: isize) -> Vec<Effect> {
+    let windows: Vec<ApplicationWindow> = state.active_list().windows().cloned().collect();
+    if windows.is_empty() {
+        return vec![];
+    }
+
+    let current_window_id = state.focused.as_ref().map(|w| w.window_id);
+    let index = relative_window_index(&windows, current_window_id, state.history.current(), delta);
+    vec![Effect::Navigate(Box::new(windows[index].clone()))]
+}
+
+fn swap_windows(state: &mut State, from_index: usize, to_index: usize) -> Vec<Effect> {
+    let list = &mut state.lists[state.active_list];
+    if from_index.max(to_index) >= list.slots.len() {
+        return vec![];
+    }
+    state.edits.record(list);
+    list.swap(from_index, to_index);
+    let cursor_delta = to_index as isize - from_index as isize;
+    vec![
+        Effect::ShowList(MoveCursor::By(cursor_delta)),
+        Effect::SaveState,
+    ]
+}
+
+/// Empties the slot at the given index and returns the window that was in it.
+fn delete_window(state: &mut State, index: usize) -> (Option<ApplicationWindow>, Vec<Effect>) {
+    let list = &mut state.lists[state.active_list];
+    if list.get(index).is_none() {
+        return (None, vec![]);
+    }
+    state.edits.record(list);
+    let window = list.set(index, None);
+    // the slot stays empty, the cursor only moves if the list got shorter
+    (
+        window,
+        vec![Effect::ShowList(MoveCursor::By(0)), Effect::SaveState],
+    )
+}
+
+/// Inserts the windows in the register at the given index. The register keeps its windows,
+/// so they can be pasted again, e.g. in another list.
+fn paste_windows(state: &mut State, index: usize, register: Option<char>) -> Vec<Effect> {
+    let list = &mut state.lists[state.active_list];
+    let windows = match register {
+        Some(name) => state.registers.get(name),
+        None => &list.clipboard,
+    };
+    if windows.is_empty() {
+        return refuse("Nothing to paste");
+    }
+
+    // a window is only in a list once
+    let windows: Vec<ApplicationWindow> = windows
+        .iter()
+        .filter(|w| list.position(w.window_id).is_none())
+        .cloned()
+        .collect();
+    let hwnd = match windows.first() {
+        Some(window) => window.window_id,
+        None => return refuse("Already in the list"),
+    };
+
+    state.edits.record(list);
+    for (slot, window) in (index..).zip(windows) {
+        // fill the slot if it's empty, otherwise make room for the window
+        match list.get(slot) {
+            Some(_) => list.insert(slot, window),
+            None => {
+                list.set(slot, Some(window));
+            }
+        }
+    }
+    vec![
+        Effect::ShowList(MoveCursor::ToWindow(hwnd)),
+        Effect::SaveState,
+    ]
+}
+
+/// Puts the windows in the named register, or in the clipboard of the active list if no
+/// register was named.
+fn store_in_register(state: &mut State, register: Option<char>, windows: Vec<ApplicationWindow>) {
+    match register {
+        Some(name) => state.registers.store(name, windows),
+        None => state.lists[state.active_list].clipboard = windows,
+    }
+}
+
+fn cycle_restore_policy(state: &mut State, index: usize) -> Vec<Effect> {
+    let list = &mut state.lists[state.active_list];
+    if list.get(index).is_none() {
+        return vec![];
+    }
+    state.edits.record(list);
+    if let Some(window) = list.get_mut(index) {
+        window.restore_policy = RestorePolicy::cycle(window.restore_policy);
+    }
+    vec![Effect::ShowList(MoveCursor::By(0)), Effect::SaveState]
+}
+
+/// Sets the label of the entry at the given index, an empty label removes it.
+fn rename_window(state: &mut State, index: usize, label: &Option<String>) -> Vec<Effect> {
+    let label = label
+        .as_deref()
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .map(str::to_string);
+    let list = &mut state.lists[state.active_list];
+    match list.get(index) {
+        Some(entry) if entry.label != label => {}
+        _ => return vec![],
+    }
+    state.edits.record(list);
+    if let Some(entry) = list.get_mut(index) {
+        entry.label = label;
+    }
+    vec![Effect::ShowList(MoveCursor::By(0)), Effect::SaveState]
+}
+
+/// Gives the entry at the given index a hotkey, or takes it away. A hotkey belongs to one
+/// entry at a time, so it has to be taken away from its entry before it can be reassigned.
+fn assign_hotkey(state: &mut State, index: usize, hotkey: &Option<Vec<Keyboard>>) -> Vec<Effect> {
+    let list = &state.lists[state.active_list];
+    match list.get(index) {
+        Some(entry) if entry.hotkey != *hotkey => {}
+        _ => return vec![],
+    }
+    if let Some(hotkey) = hotkey {
+        let owner = state
+            .lists
+            .iter()
+            .flat_map(|list| list.windows())
+            .find(|w| w.hotkey.as_ref() == Some(hotkey));
+        if let Some(owner) = owner {
+            return refuse(&format!(
+                "{} is already the hotkey of {}",
+                hotkey_string(hotkey),
+                owner.label.as_ref().unwrap_or(&owner.title)
+            ));
+        }
+    }
+
+    let list = &mut state.lists[state.active_list];
+    state.edits.record(list);
+    if let Some(entry) = list.get_mut(index) {
+        entry.hotkey = hotkey.clone();
+    }
+    vec![Effect::ShowList(MoveCursor::By(0)), Effect::SaveState]
+}
+
+/// Shows a list after one of its edits was undone or redone, switching to it if it isn't the
+/// active list.
+fn show_edited_list(state: &mut State, index: usize) -> Vec<Effect> {
+    if index != state.active_list {
+        return switch_to_list_index(state, index);
+    }
+    vec![Effect::ShowList(MoveCursor::By(0)), Effect::SaveState]
+}
+
+fn switch_to_list(state: &mut State, name: &str) -> Vec<Effect> {
+    match state.lists.iter().position(|list| list.name == name) {
+        Some(index) => switch_to_list_index(state, index),
+        None => refuse(&format!("There is no list named {:?}", name)),
+    }
+}
+
+/// Switch `delta` lists away from the active list, wrapping around.
+fn switch_list_relative(state: &mut State, delta: isize) -> Vec<Effect> {
+    let lists_len = state.lists.len() as isize;
+    let index = (state.active_list as isize + lists_len + delta % lists_len) % lists_len;
+    switch_to_list_index(state, index as usize)
+}
+
+/// Makes the list at the given index the active list, with the cursor where it was when the
+/// list was last shown.
+fn switch_to_list_index(state: &mut State, index: usize) -> Vec<Effect> {
+    if index == state.active_list || index >= state.lists.len() {
+        return vec![];
+    }
+    state.active_list = index;
+    vec![
+        Effect::ShowList(MoveCursor::To(state.lists[index].cursor)),
+        Effect::SaveState,
+    ]
+}
+
+/// Creates a list and switches to it, or switches to the list if it already exists.
+fn create_list(state: &mut State, name: &str) -> Vec<Effect> {
+    if !state.lists.iter().any(|list| list.name == name) {
+        state.lists.push(HarpoonList::new(name));
+    }
+    switch_to_list(state, name)
+}
+
+fn delete_list(state: &mut State, name: &str) -> Vec<Effect> {
+    let index = match state.lists.iter().position(|list| list.name == name) {
+        Some(index) => index,
+        None => return refuse(&format!("There is no list named {:?}", name)),
+    };
+    if state.lists.len() == 1 {
+        return refuse(&format!("Can't delete {:?}, it is the only list", name));
+    }
+
+    let was_active = index == state.active_list;
+    state.lists.remove(index);
+    state.edits.forget_list(name);
+    if index < state.active_list || state.active_list == state.lists.len() {
+        state.active_list -= 1;
+    }
+
+    let mut effects = vec![];
+    if was_active {
+        let cursor = state.active_list().cursor;
+        effects.push(Effect::ShowList(MoveCursor::To(cursor)));
+    }
+    effects.push(Effect::SaveState);
+    effects
+}
+
+/// get the index of the window to navigate to
+///
+/// If the current window is in the list of windows, then we can
+/// navigate relative to it.
+/// Otherwise, navigate to the window last navigated to.
+/// If all else fails, navigate to the first window in the list.
+fn relative_window_index(
+    windows: &[ApplicationWindow],
+    current_window_id: Option<isize>,
+    last_window_id: Option<isize>,
+    delta: isize,
+) -> usize {
+    let current_window_index = windows
+        .iter()
+        .position(|w| Some(w.window_id) == current_window_id);
+
+    let current_window_index = match current_window_index {
+        Some(index) => index,
+        None => {
+            // navigate to the window to which the user navigated most recently
+            return windows
+                .iter()
+                .position(|w| Some(w.window_id) == last_window_id)
+                .unwrap_or(0);
+        }
+    };
+
+    let windows_len = windows.len();
+
+    (current_window_index as isize + windows_len as isize + delta) as usize % windows_len
+}
+
+#[cfg(test)]
+mod tests {
+    use mki::Keyboard;
+
+    use super::{reduce, relative_window_index, Effect, State};
+    use crate::{
+        harpoon::HarpoonEvent,
+        list::HarpoonList,
+        quick_menu::MoveCursor,
+        state::PersistedState,
+        window::{mock::MockBackend, RestorePolicy, WindowBackend, WindowStatus},
+    };
+
+    /// Opens an editor, a terminal and a browser, and returns a state with a list holding them.
+    fn three_windows(backend: &MockBackend) -> State {
+        let windows = vec![
+            backend.open_window(1, "/usr/bin/editor", "editor"),
+            backend.open_window(2, "/usr/bin/terminal", "terminal"),
+            backend.open_window(3, "/usr/bin/browser", "browser"),
+        ];
+        State::new(vec![HarpoonList::with_windows("default", windows)], 0)
+    }
+
+    fn slot_ids(state: &State) -> Vec<Option<isize>> {
+        let slots = &state.active_list().slots;
+        slots
+            .iter()
+            .map(|w| w.as_ref().map(|w| w.window_id))
+            .collect()
+    }
+
+    /// Runs the effects like `Harpoon` does, as far as they concern the backend.
+    fn run(backend: &MockBackend, effects: Vec<Effect>) {
+        for effect in effects {
+            if let Effect::Navigate(window) = effect {
+                backend.navigate_to_window(&window, RestorePolicy::Geometry);
+            }
+        }
+    }
+
+    fn current_window_id(backend: &MockBackend) -> Option<isize> {
+        backend.current_window().map(|w| w.window_id)
+    }
+
+    #[test]
+    fn navigate_next_wraps_around() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+
+        for expected in [1, 2, 3, 1] {
+            state.focused = backend.current_window();
+            let effects;
+            (state, effects) = reduce(state, &HarpoonEvent::NavigateToNextWindow);
+            run(&backend, effects);
+            assert_eq!(backend.focused(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn navigate_previous_wraps_around() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+
+        backend.focus_window(1);
+        state.focused = backend.current_window();
+        let (_, effects) = reduce(state, &HarpoonEvent::NavigateToPreviousWindow);
+        assert!(matches!(&effects[..], [Effect::Navigate(window)] if window.window_id == 3));
+        run(&backend, effects);
+        assert_eq!(backend.focused(), Some(3));
+    }
+
+    #[test]
+    fn navigate_relative_from_unlisted_window_uses_last_window() {
+        let backend = MockBackend::new();
+        let windows = vec![
+            backend.open_window(1, "/usr/bin/editor", "editor"),
+            backend.open_window(2, "/usr/bin/terminal", "terminal"),
+        ];
+        backend.open_window(3, "/usr/bin/notes", "notes");
+
+        backend.focus_window(3);
+        assert_eq!(
+            relative_window_index(&windows, current_window_id(&backend), Some(2), 1),
+            1
+        );
+        // without a last window it starts at the first window
+        assert_eq!(
+            relative_window_index(&windows, current_window_id(&backend), None, 1),
+            0
+        );
+    }
+
+    #[test]
+    fn navigate_relative_skips_empty_slots() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        state.lists[0].set(1, None);
+
+        backend.focus_window(1);
+        state.focused = backend.current_window();
+        let (_, effects) = reduce(state, &HarpoonEvent::NavigateToNextWindow);
+        run(&backend, effects);
+        assert_eq!(backend.focused(), Some(3));
+    }
+
+    #[test]
+    fn empty_slot_is_explained_instead_of_navigated_to() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        state.lists[0].set(0, None);
+
+        let (_, effects) = reduce(state, &HarpoonEvent::NavigateToWindowByIndex(0));
+        assert_eq!(effects, vec![Effect::Refuse("Slot 1 is empty".to_string())]);
+    }
+
+    #[test]
+    fn swap_moves_the_cursor_along() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+
+        let (state, effects) = reduce(state, &HarpoonEvent::SwapWindows { from: 0, to: 1 });
+        assert_eq!(slot_ids(&state), vec![Some(2), Some(1), Some(3)]);
+        assert_eq!(effects[0], Effect::ShowList(MoveCursor::By(1)));
+
+        // swapping past the end does nothing
+        let (state, effects) = reduce(state, &HarpoonEvent::SwapWindows { from: 2, to: 3 });
+        assert_eq!(slot_ids(&state), vec![Some(2), Some(1), Some(3)]);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn cut_and_paste_moves_a_window_and_can_be_undone() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+
+        let cut = HarpoonEvent::CutWindow {
+            index: 0,
+            register: None,
+        };
+        let (state, _) = reduce(state, &cut);
+        assert_eq!(slot_ids(&state), vec![None, Some(2), Some(3)]);
+
+        let paste = HarpoonEvent::PasteWindow {
+            index: 3,
+            register: None,
+        };
+        let (state, effects) = reduce(state, &paste);
+        assert_eq!(slot_ids(&state), vec![None, Some(2), Some(3), Some(1)]);
+        assert_eq!(effects[0], Effect::ShowList(MoveCursor::ToWindow(1)));
+
+        // the clipboard keeps the window, but it is only in the list once
+        let (state, effects) = reduce(state, &paste);
+        assert_eq!(
+            effects,
+            vec![Effect::Refuse("Already in the list".to_string())]
+        );
+
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        assert_eq!(slot_ids(&state), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn named_registers_are_shared_by_lists() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+
+        let (state, _) = reduce(
+            state,
+            &HarpoonEvent::YankWindow {
+                index: 2,
+                register: Some('a'),
+            },
+        );
+        let (state, _) = reduce(state, &HarpoonEvent::CreateList("work".to_string()));
+        let (state, _) = reduce(
+            state,
+            &HarpoonEvent::PasteWindow {
+                index: 0,
+                register: Some('a'),
+            },
+        );
+        assert_eq!(state.active_list().name, "work");
+        assert_eq!(slot_ids(&state), vec![Some(3)]);
+        assert_eq!(state.lists[0].slots.len(), 3);
+    }
+
+    #[test]
+    fn closed_entries_of_different_programs_are_different_entries() {
+        let before_restart = MockBackend::new();
+        let persisted = PersistedState {
+            lists: vec![
+                HarpoonList::with_windows(
+                    "default",
+                    vec![before_restart.open_window(1, "/usr/bin/editor", "editor")],
+                ),
+                HarpoonList::with_windows(
+                    "work",
+                    vec![before_restart.open_window(2, "/usr/bin/terminal", "terminal")],
+                ),
+            ],
+            ..Default::default()
+        };
+        // neither program is running after the restart
+        let (lists, active_list) = persisted.into_lists(&[]);
+        let state = State::new(lists, active_list);
+
+        let (state, _) = reduce(
+            state,
+            &HarpoonEvent::YankWindow {
+                index: 0,
+                register: Some('a'),
+            },
+        );
+        let (state, _) = reduce(state, &HarpoonEvent::SwitchToList("work".to_string()));
+        let (state, effects) = reduce(
+            state,
+            &HarpoonEvent::PasteWindow {
+                index: 1,
+                register: Some('a'),
+            },
+        );
+        assert!(!matches!(effects[..], [Effect::Refuse(_)]));
+        let titles: Vec<&str> = state
+            .active_list()
+            .windows()
+            .map(|w| w.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["terminal", "editor"]);
+    }
+
+    #[test]
+    fn adding_the_focused_window_again_updates_its_entry() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+
+        backend.focus_window(2);
+        backend.set_title(2, "terminal - build");
+        state.focused = backend.current_window();
+        let (state, effects) = reduce(state, &HarpoonEvent::AddCurrentApplicationWindow);
+
+        assert_eq!(slot_ids(&state), vec![Some(1), Some(2), Some(3)]);
+        assert_eq!(
+            state.active_list().slots[1].as_ref().unwrap().title,
+            "terminal - build"
+        );
+        assert_eq!(effects[0], Effect::ShowList(MoveCursor::ToWindow(2)));
+    }
+
+    #[test]
+    fn occupied_slot_is_only_replaced_when_asked() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        backend.open_window(4, "/usr/bin/notes", "notes");
+        backend.focus_window(4);
+        state.focused = backend.current_window();
+
+        let (mut state, effects) = reduce(state, &HarpoonEvent::AddCurrentWindowToSlot(0));
+        assert_eq!(
+            effects,
+            vec![Effect::Refuse("Slot 1 is taken by editor".to_string())]
+        );
+
+        state.focused = backend.current_window();
+        let (state, _) = reduce(state, &HarpoonEvent::ReplaceSlotWithCurrentWindow(0));
+        assert_eq!(slot_ids(&state), vec![Some(4), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn grouped_windows_stay_with_their_entry() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        backend.open_window(4, "/usr/bin/browser", "docs");
+        backend.focus_window(4);
+        state.focused = backend.current_window();
+
+        let (mut state, _) = reduce(state, &HarpoonEvent::AddCurrentWindowToGroup(0));
+        let (mut state, effects) = {
+            state.focused = backend.current_window();
+            reduce(state, &HarpoonEvent::AddCurrentWindowToGroup(0))
+        };
+        assert_eq!(
+            effects,
+            vec![Effect::Refuse("docs is already in slot 1".to_string())]
+        );
+
+        // updating the entry of the primary window keeps its group
+        backend.focus_window(1);
+        state.focused = backend.current_window();
+        let (state, _) = reduce(state, &HarpoonEvent::AddCurrentApplicationWindow);
+        let members = &state.active_list().slots[0].as_ref().unwrap().group_members;
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].window_id, 4);
+
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        assert!(state.active_list().slots[0]
+            .as_ref()
+            .unwrap()
+            .group_members
+            .is_empty());
+    }
+
+    #[test]
+    fn renaming_sets_and_clears_the_label() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+
+        let rename = |index, label: &str| HarpoonEvent::RenameWindow {
+            index,
+            label: Some(label.to_string()),
+        };
+        let (state, _) = reduce(state, &rename(1, " build "));
+        let label = |state: &State| state.active_list().slots[1].as_ref().unwrap().label.clone();
+        assert_eq!(label(&state), Some("build".to_string()));
+
+        let (state, effects) = reduce(state, &rename(1, "build"));
+        assert_eq!(effects, vec![]);
+        let (state, _) = reduce(state, &rename(1, ""));
+        assert_eq!(label(&state), None);
+
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        assert_eq!(label(&state), Some("build".to_string()));
+    }
+
+    #[test]
+    fn hotkeys_navigate_to_their_entry_in_any_list() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        let notes = backend.open_window(4, "/usr/bin/notes", "notes");
+        state
+            .lists
+            .push(HarpoonList::with_windows("notes", vec![notes]));
+
+        let hotkey = vec![Keyboard::LeftControl, Keyboard::E];
+        let assign = |index, hotkey: &Vec<Keyboard>| HarpoonEvent::AssignHotkey {
+            index,
+            hotkey: Some(hotkey.clone()),
+        };
+        let (state, _) = reduce(state, &assign(2, &hotkey));
+        // a hotkey belongs to one entry
+        let (state, effects) = reduce(state, &assign(0, &hotkey));
+        assert!(matches!(effects[..], [Effect::Refuse(_)]));
+
+        // moving the entry keeps its hotkey
+        let (state, _) = reduce(state, &HarpoonEvent::SwapWindows { from: 2, to: 0 });
+        let (mut state, effects) = reduce(
+            state,
+            &HarpoonEvent::NavigateToWindowByHotkey(hotkey.clone()),
+        );
+        run(&backend, effects);
+        assert_eq!(current_window_id(&backend), Some(3));
+
+        state.active_list = 1;
+        let (state, effects) = reduce(
+            state,
+            &HarpoonEvent::NavigateToWindowByHotkey(hotkey.clone()),
+        );
+        run(&backend, effects);
+        assert_eq!(state.active_list, 0);
+        assert_eq!(current_window_id(&backend), Some(3));
+    }
+
+    #[test]
+    fn rebinding_moves_the_entry_to_another_window_of_its_program() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        state.focused = Some(backend.open_window(5, "/usr/bin/browser", "browser"));
+        let (state, _) = reduce(state, &HarpoonEvent::RebindToCurrentWindow);
+        assert_eq!(slot_ids(&state), vec![Some(1), Some(2), Some(5)]);
+
+        let (mut state, effects) = reduce(state, &HarpoonEvent::RebindToCurrentWindow);
+        assert!(matches!(effects[..], [Effect::Refuse(_)]));
+        state.focused = Some(backend.open_window(6, "/usr/bin/notes", "notes"));
+        let (state, effects) = reduce(state, &HarpoonEvent::RebindToCurrentWindow);
+        assert!(matches!(effects[..], [Effect::Refuse(_)]));
+
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        assert_eq!(slot_ids(&state), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn rebinding_points_the_history_and_registers_at_the_new_window() {
+        let backend = MockBackend::new();
+        let mut state = three_windows(&backend);
+        state.history.visit(3);
+        let browser = state.lists[0].get(2).cloned().unwrap();
+        state.registers.store('a', vec![browser]);
+
+        state.focused = Some(backend.open_window(5, "/usr/bin/browser", "browser"));
+        let (state, _) = reduce(state, &HarpoonEvent::RebindToCurrentWindow);
+        assert_eq!(state.history.current(), Some(5));
+        assert_eq!(state.registers.get('a')[0].window_id, 5);
+
+        let (state, _) = reduce(state, &HarpoonEvent::Undo);
+        assert_eq!(slot_ids(&state), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn readding_an_entry_keeps_its_settings() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+        let (mut state, _) = reduce(state, &HarpoonEvent::CycleRestorePolicy(0));
+        state.lists[0].get_mut(0).unwrap().status = WindowStatus::Rebound;
+
+        backend.set_title(1, "main.rs - editor");
+        backend.focus_window(1);
+        state.focused = backend.current_window();
+        let (state, effects) = reduce(state, &HarpoonEvent::AddCurrentApplicationWindow);
+        assert!(effects.contains(&Effect::SaveState));
+        let entry = state.active_list().get(0).unwrap();
+        assert_eq!(entry.title, "main.rs - editor");
+        assert_eq!(entry.restore_policy, Some(RestorePolicy::Geometry));
+        assert_eq!(entry.status, WindowStatus::Rebound);
+
+        // adding it again changes nothing
+        let (state, effects) = reduce(state, &HarpoonEvent::AddCurrentApplicationWindow);
+        assert_eq!(effects, vec![]);
+
+        let (state, _) = reduce(state, &HarpoonEvent::AddCurrentWindowToSlot(4));
+        assert_eq!(
+            slot_ids(&state),
+            vec![None, Some(2), Some(3), None, Some(1)]
+        );
+        let entry = state.active_list().get(4).unwrap();
+        assert_eq!(entry.restore_policy, Some(RestorePolicy::Geometry));
+        assert_eq!(entry.status, WindowStatus::Rebound);
+    }
+
+    #[test]
+    fn edits_of_a_deleted_list_are_forgotten() {
+        let backend = MockBackend::new();
+        let state = three_windows(&backend);
+        let (state, _) = reduce(state, &HarpoonEvent::CreateList("work".to_string()));
+        let (mut state, _) = reduce(state, &HarpoonEvent::SwitchToList("default".to_string()));
+        state.lists[1] = HarpoonList::with_windows(
+            "work",
+            vec![backend.open_window(4, "/usr/bin/notes", "notes")],
+        );
+        let (state, _) = reduce(state, &HarpoonEvent::SwitchToList("work".to_string()));
+        let (state, _) = reduce(state, &HarpoonEvent::DeleteWindow(0));
+
+        let (state, _) = reduce(state, &HarpoonEvent::DeleteList("work".to_string()));
+        let (state, _) = reduce(state, &HarpoonEvent::CreateList("work".to_string()));
+        let (state, effects) = reduce(state, &HarpoonEvent::Undo);
+        assert_eq!(effects, vec![Effect::Refuse("Nothing to undo".to_string())]);
+        assert_eq!(slot_ids(&state), vec![]);
+    }
+}
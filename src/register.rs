@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::window::ApplicationWindow;
+
+/// The named registers (`"a` to `"z`) that windows can be cut, yanked and pasted with.
+///
+/// Unlike the clipboard of a list, named registers are shared by all lists, so
+/// they can be used to copy windows from one list to another. Like in vim,
+/// naming a register with an uppercase letter appends to it instead of
+/// replacing what it holds.
+#[derive(Debug, Default)]
+pub struct Registers {
+    named: HashMap<char, Vec<ApplicationWindow>>,
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether `name` names a register.
+    pub fn is_register(name: char) -> bool {
+        name.is_ascii_alphabetic()
+    }
+
+    /// Puts the windows in the register, or adds them to it if `name` is uppercase.
+    pub fn store(&mut self, name: char, windows: Vec<ApplicationWindow>) {
+        let register = self.named.entry(name.to_ascii_lowercase()).or_default();
+        if !name.is_ascii_uppercase() {
+            register.clear();
+        }
+        register.extend(windows);
+    }
+
+    /// Returns the windows in the register, which is empty if nothing was stored in it yet.
+    pub fn get(&self, name: char) -> &[ApplicationWindow] {
+        self.named
+            .get(&name.to_ascii_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Points the registers at the new id of a window, e.g. after it was relaunched.
+    pub fn replace(&mut self, old_id: isize, new_id: isize) {
+        for window in self.named.values_mut().flatten() {
+            if window.window_id == old_id {
+                window.window_id = new_id;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Registers;
+    use crate::window::mock::MockBackend;
+
+    #[test]
+    fn uppercase_register_appends() {
+        let backend = MockBackend::new();
+        let editor = backend.open_window(1, "/usr/bin/editor", "editor");
+        let terminal = backend.open_window(2, "/usr/bin/terminal", "terminal");
+        let mut registers = Registers::new();
+
+        registers.store('a', vec![editor.clone()]);
+        registers.store('A', vec![terminal.clone()]);
+        assert_eq!(registers.get('a'), &[editor, terminal.clone()]);
+
+        registers.store('a', vec![terminal.clone()]);
+        assert_eq!(registers.get('A'), &[terminal]);
+        assert!(registers.get('b').is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+
+use crate::{
+    harpoon::HarpoonEvent,
+    list::{HarpoonList, DEFAULT_LIST_NAME},
+    recorder::{self, RecordedEntry},
+    reducer::{self, Effect, State},
+    state::PersistedState,
+    window::{mock::MockBackend, ApplicationWindow, RestorePolicy, WindowBackend},
+};
+
+/// The lists a recording ends up with, and what couldn't be replayed.
+pub struct Replayed {
+    pub state: PersistedState,
+    /// events that `Harpoon` carries out itself, like applying a layout or recapturing a
+    /// window's geometry, together with their position in the recording
+    pub skipped: Vec<(usize, HarpoonEvent)>,
+}
+
+/// Runs the events of a recording against a mock window backend and returns the lists they
+/// end up with.
+///
+/// Events go through the same reducer `Harpoon` dispatches them to, with the focused window
+/// that was recorded with them. Events the reducer doesn't handle are skipped, and those that
+/// can change the lists are reported. Changes harpoon makes without an event, like marking
+/// closed windows or binding an entry to a relaunched window, aren't recorded at all. A start
+/// entry replaces the lists with the ones that were recorded, so a recording that spans
+/// several runs of harpoon replays every run from its own start.
+pub fn replay(entries: Vec<RecordedEntry>) -> Replayed {
+    let backend = MockBackend::new();
+    let mut state = State::new(vec![HarpoonList::new(DEFAULT_LIST_NAME)], 0);
+    let mut skipped = vec![];
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        match entry {
+            RecordedEntry::Start { start, .. } => {
+                let windows = start.lists.iter().flat_map(|list| list.windows());
+                for window in windows.filter(|w| w.window_id != 0) {
+                    backend.add_window(window.clone());
+                }
+                let (lists, active_list) = start.into_lists(&backend.enumerate_windows());
+                state = State::new(lists, active_list);
+            }
+            RecordedEntry::Event { focused, event, .. } => {
+                if !reducer::handles(&event) {
+                    if !only_affects_the_ui(&event) {
+                        skipped.push((index + 1, event));
+                    }
+                    continue;
+                }
+                let focused = focused.map(|window| *window);
+                if let Some(window) = &focused {
+                    if !backend.is_alive(window.window_id) {
+                        backend.add_window(window.clone());
+                    }
+                    backend.focus_window(window.window_id);
+                }
+                state.focused = focused;
+
+                let effects;
+                (state, effects) = reducer::reduce(state, &event);
+                for effect in effects {
+                    if let Effect::Navigate(window) = effect {
+                        backend.navigate_to_window(&window, RestorePolicy::Geometry);
+                        state.history.visit(window.window_id);
+                    }
+                }
+            }
+        }
+    }
+
+    Replayed {
+        state: state.to_persisted(),
+        skipped,
+    }
+}
+
+/// Returns whether the event only changes the quick menu or harpoon itself, so skipping it
+/// can't make the replayed lists differ from the recorded ones.
+fn only_affects_the_ui(event: &HarpoonEvent) -> bool {
+    matches!(
+        event,
+        HarpoonEvent::ToggleQuickMenu
+            | HarpoonEvent::CloseQuickMenu
+            | HarpoonEvent::QuickMenuEvent(_)
+            | HarpoonEvent::ToggleInhibit
+            | HarpoonEvent::Quit
+    )
+}
+
+/// Describes how the lists of `actual` differ from those of `expected`, one line per
+/// difference. Entries are compared by window id, title and program, so moving a window
+/// doesn't count as a difference.
+pub fn diff(expected: &PersistedState, actual: &PersistedState) -> Vec<String> {
+    let mut differences = vec![];
+
+    for expected_list in expected.lists.iter() {
+        let actual_list = match actual.lists.iter().find(|l| l.name == expected_list.name) {
+            Some(list) => list,
+            None => {
+                differences.push(format!("list {:?} is missing", expected_list.name));
+                continue;
+            }
+        };
+        let slots = expected_list.slots.len().max(actual_list.slots.len());
+        for slot in 0..slots {
+            let expected_window = describe(expected_list.slots.get(slot).and_then(Option::as_ref));
+            let actual_window = describe(actual_list.slots.get(slot).and_then(Option::as_ref));
+            if expected_window != actual_window {
+                differences.push(format!(
+                    "list {:?} slot {}: expected {}, got {}",
+                    expected_list.name,
+                    slot + 1,
+                    expected_window,
+                    actual_window
+                ));
+            }
+        }
+    }
+
+    for actual_list in actual.lists.iter() {
+        if !expected.lists.iter().any(|l| l.name == actual_list.name) {
+            differences.push(format!("list {:?} is unexpected", actual_list.name));
+        }
+    }
+
+    let active_name =
+        |state: &PersistedState| state.lists.get(state.active_list).map(|l| l.name.clone());
+    if active_name(expected) != active_name(actual) {
+        differences.push(format!(
+            "expected {:?} to be the active list, got {:?}",
+            active_name(expected),
+            active_name(actual)
+        ));
+    }
+
+    differences
+}
+
+fn describe(window: Option<&ApplicationWindow>) -> String {
+    match window {
+        Some(window) => format!(
+            "{:?} ({}, {})",
+            window.title, window.process_path, window.window_id
+        ),
+        None => "an empty slot".to_string(),
+    }
+}
+
+/// Replays the recording and compares the result with the state file at `expected`, or
+/// prints the resulting lists if there is nothing to compare with. Returns whether the
+/// result matched.
+pub fn run(recording: &str, expected: Option<&str>) -> Result<bool> {
+    let Replayed {
+        state: actual,
+        skipped,
+    } = replay(recorder::load_recording(recording)?);
+    for (position, event) in skipped.iter() {
+        println!(
+            "Entry {} was not replayed, harpoon handles it outside the reducer: {:?}",
+            position, event
+        );
+    }
+
+    let expected = match expected {
+        Some(path) => path,
+        None => {
+            println!("{}", serde_json::to_string_pretty(&actual)?);
+            return Ok(true);
+        }
+    };
+    let expected: PersistedState = serde_json::from_str(&std::fs::read_to_string(expected)?)?;
+
+    let differences = diff(&expected, &actual);
+    for difference in differences.iter() {
+        println!("{}", difference);
+    }
+    if differences.is_empty() {
+        println!("The replayed lists match");
+    }
+    Ok(differences.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, replay};
+    use crate::{
+        harpoon::HarpoonEvent,
+        list::HarpoonList,
+        recorder::{load_recording, Recorder},
+        state::PersistedState,
+        window::mock::MockBackend,
+    };
+
+    #[test]
+    fn recorded_session_replays_to_the_same_lists() {
+        let backend = MockBackend::new();
+        let editor = backend.open_window(1, "/usr/bin/editor", "editor");
+        let terminal = backend.open_window(2, "/usr/bin/terminal", "terminal");
+        let notes = backend.open_window(3, "/usr/bin/notes", "notes");
+        let start = PersistedState {
+            lists: vec![HarpoonList::with_windows(
+                "default",
+                vec![editor.clone(), terminal.clone()],
+            )],
+            ..Default::default()
+        };
+
+        let path =
+            std::env::temp_dir().join(format!("harpoon-replay-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut recorder = Recorder::open(path, start).unwrap();
+        recorder
+            .record(
+                &HarpoonEvent::AddCurrentApplicationWindow,
+                Some(notes.clone()),
+            )
+            .unwrap();
+        recorder
+            .record(&HarpoonEvent::ApplyLayout("coding".to_string()), None)
+            .unwrap();
+        recorder
+            .record(&HarpoonEvent::ToggleQuickMenu, None)
+            .unwrap();
+        recorder
+            .record(&HarpoonEvent::SwapWindows { from: 0, to: 2 }, None)
+            .unwrap();
+        let entries = load_recording(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let replayed = replay(entries);
+        assert_eq!(
+            replayed.skipped,
+            vec![(3, HarpoonEvent::ApplyLayout("coding".to_string()))]
+        );
+        let actual = replayed.state;
+        let mut expected = PersistedState {
+            lists: vec![HarpoonList::with_windows(
+                "default",
+                vec![notes, terminal, editor.clone()],
+            )],
+            ..Default::default()
+        };
+        assert_eq!(diff(&expected, &actual), Vec::<String>::new());
+
+        expected.lists[0].set(0, None);
+        assert_eq!(
+            diff(&expected, &actual),
+            vec!["list \"default\" slot 1: expected an empty slot, got \"notes\" (/usr/bin/notes, 3)"]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    list::{HarpoonList, DEFAULT_LIST_NAME},
+    window::{self, ApplicationWindow, WindowStatus},
+};
+
+const STATE_FILE_PATH: &str = "state.json";
+
+/// Closed windows used to be marked by prefixing their process name with this.
+const LEGACY_CLOSED_PREFIX: &str = "[CLOSED] ";
+
+/// The part of harpoon that is kept across restarts.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct PersistedState {
+    #[serde(default)]
+    pub lists: Vec<HarpoonList>,
+    #[serde(default)]
+    pub active_list: usize,
+    /// the single window list of state files written before named lists existed
+    #[serde(default, skip_serializing)]
+    pub windows: Vec<ApplicationWindow>,
+}
+
+impl PersistedState {
+    /// Returns the stored lists bound to the live windows, and the index of the active list.
+    ///
+    /// There is always at least one list, and the active index always points at one.
+    pub fn into_lists(self, live: &[ApplicationWindow]) -> (Vec<HarpoonList>, usize) {
+        let mut lists = self.lists;
+        if lists.is_empty() {
+            lists.push(HarpoonList::with_windows(DEFAULT_LIST_NAME, self.windows));
+        }
+
+        // a window that is in several lists gets the same closed id in all of them
+        let mut closed_ids = HashMap::new();
+        for list in lists.iter_mut() {
+            // rematch the windows and their group members on their own and put them back in
+            // their slots afterwards
+            let group_sizes: Vec<usize> = list.windows().map(|w| w.group_members.len()).collect();
+            let windows: Vec<ApplicationWindow> = list
+                .windows()
+                .flat_map(|window| {
+                    let mut window = window.clone();
+                    let members = std::mem::take(&mut window.group_members);
+                    std::iter::once(window).chain(members)
+                })
+                .collect();
+            let mut rematched = rematch(windows, live, &mut closed_ids).into_iter();
+            for (window, group_size) in list.slots.iter_mut().flatten().zip(group_sizes) {
+                *window = rematched.next().unwrap();
+                window.group_members = rematched.by_ref().take(group_size).collect();
+            }
+        }
+
+        let active_list = self.active_list.min(lists.len() - 1);
+        (lists, active_list)
+    }
+}
+
+pub fn load_state_from_disk() -> Result<PersistedState> {
+    if !std::path::Path::new(STATE_FILE_PATH).exists() {
+        return Ok(PersistedState::default());
+    }
+    let state = std::fs::read_to_string(STATE_FILE_PATH)?;
+    Ok(serde_json::from_str(&state)?)
+}
+
+pub fn save_state_to_disk(state: &PersistedState) -> Result<()> {
+    let state = serde_json::to_string_pretty(state)?;
+    std::fs::write(STATE_FILE_PATH, state)?;
+    Ok(())
+}
+
+/// Binds stored windows to the windows that are currently running.
+///
+/// Window ids do not survive a restart, so every stored window is matched to a
+/// live window with the same process path and title. Windows for which that
+/// fails fall back to the first unclaimed live window of the same process.
+/// Stored windows without any match keep their saved data but get a closed window
+/// id and are marked as closed, so they are never confused with an unrelated
+/// window that happens to reuse their old id, or with each other.
+pub fn rematch_windows(
+    stored: Vec<ApplicationWindow>,
+    live: &[ApplicationWindow],
+) -> Vec<ApplicationWindow> {
+    rematch(stored, live, &mut HashMap::new())
+}
+
+/// Like `rematch_windows`, reusing the closed id given to a stored window id before.
+fn rematch(
+    stored: Vec<ApplicationWindow>,
+    live: &[ApplicationWindow],
+    closed_ids: &mut HashMap<isize, isize>,
+) -> Vec<ApplicationWindow> {
+    let mut claimed = vec![false; live.len()];
+    let mut matches: Vec<Option<usize>> = vec![None; stored.len()];
+
+    // exact matches first, so they can't be stolen by a process-only match
+    for (stored_index, window) in stored.iter().enumerate() {
+        matches[stored_index] = claim(live, &mut claimed, |w| {
+            w.process_path == window.process_path && w.title == window.title
+        });
+    }
+
+    for (stored_index, window) in stored.iter().enumerate() {
+        if matches[stored_index].is_some() {
+            continue;
+        }
+        matches[stored_index] = claim(live, &mut claimed, |w| {
+            w.process_path == window.process_path
+        });
+    }
+
+    stored
+        .into_iter()
+        .zip(matches)
+        .map(|(mut window, live_index)| {
+            if let Some(process_name) = window.process_name.strip_prefix(LEGACY_CLOSED_PREFIX) {
+                window.process_name = process_name.to_string();
+            }
+            match live_index {
+                Some(index) => {
+                    window.window_id = live[index].window_id;
+                    window.title = live[index].title.clone();
+                    window.status = WindowStatus::Alive;
+                }
+                None => {
+                    window.window_id = match window.window_id {
+                        // every closed window of an old state file has id 0
+                        0 => window::closed_window_id(),
+                        id => *closed_ids
+                            .entry(id)
+                            .or_insert_with(window::closed_window_id),
+                    };
+                    window.status = WindowStatus::Closed;
+                }
+            }
+            window
+        })
+        .collect()
+}
+
+fn claim(
+    live: &[ApplicationWindow],
+    claimed: &mut [bool],
+    predicate: impl Fn(&ApplicationWindow) -> bool,
+) -> Option<usize> {
+    let index = live
+        .iter()
+        .enumerate()
+        .position(|(index, window)| !claimed[index] && predicate(window))?;
+    claimed[index] = true;
+    Some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rematch_windows;
+    use crate::window::{is_closed_id, mock::MockBackend, WindowBackend, WindowStatus};
+
+    #[test]
+    fn rematch_prefers_same_title_and_clears_missing_windows() {
+        let before_restart = MockBackend::new();
+        let stored = vec![
+            before_restart.open_window(1, "/usr/bin/terminal", "build"),
+            before_restart.open_window(2, "/usr/bin/terminal", "server"),
+            before_restart.open_window(3, "/usr/bin/editor", "notes"),
+        ];
+
+        let after_restart = MockBackend::new();
+        after_restart.open_window(10, "/usr/bin/terminal", "server");
+        after_restart.open_window(11, "/usr/bin/terminal", "build");
+
+        let windows = rematch_windows(stored, &after_restart.enumerate_windows());
+
+        let ids: Vec<isize> = windows.iter().map(|w| w.window_id).collect();
+        assert_eq!(ids[..2], [11, 10]);
+        assert!(is_closed_id(ids[2]));
+        assert_eq!(windows[2].title, "notes");
+        assert_eq!(windows[2].status, WindowStatus::Closed);
+    }
+
+    #[test]
+    fn rematch_strips_legacy_closed_prefix() {
+        let before_restart = MockBackend::new();
+        let mut stored = before_restart.open_window(1, "/usr/bin/terminal", "build");
+        stored.process_name = format!("[CLOSED] {}", stored.process_name);
+
+        let after_restart = MockBackend::new();
+        after_restart.open_window(10, "/usr/bin/terminal", "build");
+
+        let windows = rematch_windows(vec![stored], &after_restart.enumerate_windows());
+
+        assert_eq!(windows[0].process_name, "terminal");
+        assert_eq!(windows[0].status, WindowStatus::Alive);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{list::HarpoonList, window::ApplicationWindow};
+
+/// How many edits can be undone, older edits are forgotten.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// The slots and clipboard of a list before or after an edit.
+#[derive(Debug, Clone)]
+struct ListSnapshot {
+    list: String,
+    slots: Vec<Option<ApplicationWindow>>,
+    clipboard: Vec<ApplicationWindow>,
+}
+
+impl ListSnapshot {
+    fn of(list: &HarpoonList) -> Self {
+        Self {
+            list: list.name.clone(),
+            slots: list.slots.clone(),
+            clipboard: list.clipboard.clone(),
+        }
+    }
+
+    /// Puts the snapshot back into the list and returns a snapshot of what was replaced.
+    fn restore(self, list: &mut HarpoonList) -> Self {
+        let replaced = Self::of(list);
+        list.slots = self.slots;
+        list.clipboard = self.clipboard;
+        replaced
+    }
+}
+
+/// The edits made to the lists, so they can be undone and redone.
+///
+/// Every edit is stored as a snapshot of the edited list from before the edit,
+/// the clipboard included, so undoing a cut also takes the window out of the
+/// clipboard again. Making a new edit drops the edits that were undone.
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<ListSnapshot>,
+    redo: Vec<ListSnapshot>,
+}
+
+impl UndoHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the list as it is before an edit, call this right before changing it.
+    pub fn record(&mut self, list: &HarpoonList) {
+        self.undo.push(ListSnapshot::of(list));
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Reverts the most recent edit and returns the index of the list it was made to.
+    pub fn undo(&mut self, lists: &mut [HarpoonList]) -> Option<usize> {
+        Self::step(&mut self.undo, &mut self.redo, lists)
+    }
+
+    /// Makes the most recently undone edit again and returns the index of the list it was
+    /// made to.
+    pub fn redo(&mut self, lists: &mut [HarpoonList]) -> Option<usize> {
+        Self::step(&mut self.redo, &mut self.undo, lists)
+    }
+
+    /// Points the recorded edits at the new id of a window, e.g. after it was relaunched.
+    pub fn replace(&mut self, old_id: isize, new_id: isize) {
+        let snapshots = self.undo.iter_mut().chain(self.redo.iter_mut());
+        for snapshot in snapshots {
+            let windows = snapshot.slots.iter_mut().flatten();
+            for window in windows.chain(snapshot.clipboard.iter_mut()) {
+                if window.window_id == old_id {
+                    window.window_id = new_id;
+                }
+            }
+        }
+    }
+
+    /// Forgets the edits made to a list that is deleted, so they aren't undone in a new list
+    /// that gets the same name.
+    pub fn forget_list(&mut self, name: &str) {
+        self.undo.retain(|snapshot| snapshot.list != name);
+        self.redo.retain(|snapshot| snapshot.list != name);
+    }
+
+    /// Restores the newest snapshot in `from` and records the state it replaced in `to`.
+    fn step(
+        from: &mut Vec<ListSnapshot>,
+        to: &mut Vec<ListSnapshot>,
+        lists: &mut [HarpoonList],
+    ) -> Option<usize> {
+        while let Some(snapshot) = from.pop() {
+            let index = match lists.iter().position(|list| list.name == snapshot.list) {
+                Some(index) => index,
+                None => continue,
+            };
+            to.push(snapshot.restore(&mut lists[index]));
+            return Some(index);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UndoHistory;
+    use crate::{list::HarpoonList, window::mock::MockBackend};
+
+    #[test]
+    fn undo_brings_back_every_cut_window() {
+        let backend = MockBackend::new();
+        let mut lists = vec![HarpoonList::with_windows(
+            "test",
+            vec![
+                backend.open_window(1, "/usr/bin/editor", "editor"),
+                backend.open_window(2, "/usr/bin/terminal", "terminal"),
+            ],
+        )];
+        let mut history = UndoHistory::new();
+
+        // the second cut replaces the first window in the clipboard
+        for slot in [0, 1] {
+            history.record(&lists[0]);
+            lists[0].clipboard = lists[0].set(slot, None).into_iter().collect();
+        }
+        assert!(lists[0].slots.is_empty());
+
+        assert_eq!(history.undo(&mut lists), Some(0));
+        assert_eq!(history.undo(&mut lists), Some(0));
+        assert_eq!(history.undo(&mut lists), None);
+        assert_eq!(lists[0].position(1), Some(0));
+        assert_eq!(lists[0].position(2), Some(1));
+        assert!(lists[0].clipboard.is_empty());
+
+        assert_eq!(history.redo(&mut lists), Some(0));
+        assert_eq!(lists[0].get(0), None);
+        assert_eq!(lists[0].clipboard[0].window_id, 1);
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::Mutex;
+
+use crate::window::{
+    ApplicationWindow, Monitor, RestorePolicy, WindowBackend, WindowPosition, WindowState,
+    WindowStatus,
+};
+
+/// An in-memory window backend.
+///
+/// Windows only exist in the backend's own list, which makes it possible to
+/// exercise harpoon's list and navigation logic without a windowing system.
+#[derive(Default)]
+pub struct MockBackend {
+    state: Mutex<MockState>,
+}
+
+#[derive(Default)]
+struct MockState {
+    windows: Vec<ApplicationWindow>,
+    focused: Option<isize>,
+    unresponsive: Vec<isize>,
+    monitors: Vec<Monitor>,
+}
+
+impl MockState {
+    /// Moves the window to the top of the stacking order, which is the front of the list.
+    fn raise(&mut self, window_id: isize) {
+        if let Some(index) = self.windows.iter().position(|w| w.window_id == window_id) {
+            let window = self.windows.remove(index);
+            self.windows.insert(0, window);
+        }
+    }
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a window and returns it, the window is not focused.
+    pub fn open_window(
+        &self,
+        window_id: isize,
+        process_path: &str,
+        title: &str,
+    ) -> ApplicationWindow {
+        let process_name = std::path::Path::new(process_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        let window = ApplicationWindow {
+            window_id,
+            title: title.to_string(),
+            process_path: process_path.to_string(),
+            position: WindowPosition {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+            },
+            state: WindowState::Normal,
+            process_name,
+            status: WindowStatus::Alive,
+            restore_policy: None,
+            placement: None,
+            group_members: vec![],
+            label: None,
+            hotkey: None,
+        };
+
+        self.state.lock().unwrap().windows.push(window.clone());
+        window
+    }
+
+    /// Adds the window as it is, replacing the window with the same id if there is one.
+    pub fn add_window(&self, window: ApplicationWindow) {
+        let mut state = self.state.lock().unwrap();
+        state.windows.retain(|w| w.window_id != window.window_id);
+        state.windows.push(window);
+    }
+
+    /// Closes the window with the given id.
+    pub fn close_window(&self, window_id: isize) {
+        let mut state = self.state.lock().unwrap();
+        state.windows.retain(|w| w.window_id != window_id);
+        if state.focused == Some(window_id) {
+            state.focused = None;
+        }
+    }
+
+    /// Marks the window with the given id as hung, or as responding again.
+    pub fn set_responsive(&self, window_id: isize, responsive: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.unresponsive.retain(|id| *id != window_id);
+        if !responsive {
+            state.unresponsive.push(window_id);
+        }
+    }
+
+    /// Replaces the connected monitors.
+    pub fn set_monitors(&self, monitors: Vec<Monitor>) {
+        self.state.lock().unwrap().monitors = monitors;
+    }
+
+    /// Changes the title of the window with the given id.
+    pub fn set_title(&self, window_id: isize, title: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(window) = state.windows.iter_mut().find(|w| w.window_id == window_id) {
+            window.title = title.to_string();
+        }
+    }
+
+    /// Returns the id of the focused window.
+    pub fn focused(&self) -> Option<isize> {
+        self.state.lock().unwrap().focused
+    }
+}
+
+impl WindowBackend for MockBackend {
+    fn current_window(&self) -> Option<ApplicationWindow> {
+        let state = self.state.lock().unwrap();
+        let focused = state.focused?;
+        state
+            .windows
+            .iter()
+            .find(|w| w.window_id == focused)
+            .cloned()
+    }
+
+    fn is_alive(&self, window_id: isize) -> bool {
+        let state = self.state.lock().unwrap();
+        state.windows.iter().any(|w| w.window_id == window_id)
+    }
+
+    fn is_responsive(&self, window_id: isize) -> bool {
+        !self.state.lock().unwrap().unresponsive.contains(&window_id)
+    }
+
+    fn window_title(&self, window_id: isize) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .windows
+            .iter()
+            .find(|w| w.window_id == window_id)
+            .map(|w| w.title.clone())
+    }
+
+    fn window_process_path(&self, window_id: isize) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .windows
+            .iter()
+            .find(|w| w.window_id == window_id)
+            .map(|w| w.process_path.clone())
+    }
+
+    fn navigate_to_window(&self, window: &ApplicationWindow, policy: RestorePolicy) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(live) = state
+            .windows
+            .iter_mut()
+            .find(|w| w.window_id == window.window_id)
+        {
+            if policy == RestorePolicy::Geometry {
+                live.position = window.position.clone();
+            }
+            if policy != RestorePolicy::FocusOnly {
+                live.state = window.state.clone();
+            }
+            state.focused = Some(window.window_id);
+        }
+        state.raise(window.window_id);
+    }
+
+    fn move_windows(&self, windows: &[ApplicationWindow]) {
+        let mut state = self.state.lock().unwrap();
+        for window in windows {
+            if let Some(live) = state
+                .windows
+                .iter_mut()
+                .find(|w| w.window_id == window.window_id)
+            {
+                live.position = window.position.clone();
+                live.state = window.state.clone();
+            }
+        }
+    }
+
+    fn focus_window(&self, window_id: isize) {
+        if self.is_alive(window_id) {
+            let mut state = self.state.lock().unwrap();
+            state.focused = Some(window_id);
+            state.raise(window_id);
+        }
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        self.state.lock().unwrap().monitors.clone()
+    }
+
+    fn enumerate_windows(&self) -> Vec<ApplicationWindow> {
+        self.state.lock().unwrap().windows.clone()
+    }
+}
@@ -0,0 +1,676 @@
+use std::{path::Path, sync::mpsc::channel, thread};
+
+use active_win_pos_rs::get_active_window;
+use windows::{
+    core::{PCSTR, PWSTR},
+    Win32::{
+        Foundation::{CloseHandle, BOOL, HWND, LPARAM, LRESULT, MAX_PATH, RECT, WPARAM},
+        Graphics::Gdi::{
+            EnumDisplayMonitors, GetMonitorInfoW, HBRUSH, HDC, HMONITOR, MONITORINFO,
+            MONITORINFOEXW,
+        },
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            Threading::{
+                AttachThreadInput, GetCurrentThreadId, OpenProcess, QueryFullProcessImageNameW,
+                PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+        UI::{
+            HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+            Input::KeyboardAndMouse::SetActiveWindow,
+            WindowsAndMessaging::{
+                BeginDeferWindowPos, BringWindowToTop, CreateWindowExA, DefWindowProcA,
+                DeferWindowPos, DispatchMessageA, EndDeferWindowPos, EnumWindows,
+                GetForegroundWindow, GetMessageA, GetWindowPlacement, GetWindowRect,
+                GetWindowTextW, GetWindowThreadProcessId, IsHungAppWindow, IsIconic, IsWindow,
+                IsWindowVisible, LoadCursorW, LoadImageA, PostQuitMessage, RegisterClassA,
+                SetForegroundWindow, ShowWindow, TranslateMessage, HICON, HWND_TOP, IDC_ARROW,
+                IMAGE_ICON, LR_LOADFROMFILE, MONITORINFOF_PRIMARY, MSG, SWP_DRAWFRAME,
+                SWP_NOACTIVATE, SWP_NOZORDER, SWP_SHOWWINDOW, SW_HIDE, SW_MAXIMIZE, SW_NORMAL,
+                SW_RESTORE, SW_SHOWMAXIMIZED, SW_SHOWMINIMIZED, WINDOWPLACEMENT, WINDOW_EX_STYLE,
+                WINDOW_STYLE, WM_DESTROY, WM_NULL, WNDCLASSA, WNDCLASS_STYLES,
+            },
+        },
+    },
+};
+
+use crate::{
+    assets::get_app_icon_filepath,
+    window::{
+        is_closed_id, ApplicationWindow, Monitor, RestorePolicy, WindowBackend, WindowPosition,
+        WindowState, WindowStatus,
+    },
+};
+
+/// Window backend for the Win32 API.
+pub struct Win32Backend {
+    /// Handle of the hidden window that owns harpoon's tray notifications.
+    _app_window: isize,
+}
+
+impl Win32Backend {
+    pub fn new() -> Self {
+        Self {
+            _app_window: create_window(),
+        }
+    }
+}
+
+impl WindowBackend for Win32Backend {
+    fn current_window(&self) -> Option<ApplicationWindow> {
+        get_current_window()
+    }
+
+    fn is_alive(&self, window_id: isize) -> bool {
+        !is_closed_id(window_id) && unsafe { IsWindow(HWND(window_id)).as_bool() }
+    }
+
+    fn is_responsive(&self, window_id: isize) -> bool {
+        unsafe { !IsHungAppWindow(HWND(window_id)).as_bool() }
+    }
+
+    fn window_title(&self, window_id: isize) -> Option<String> {
+        get_window_title(window_id)
+    }
+
+    fn window_process_path(&self, window_id: isize) -> Option<String> {
+        if !self.is_alive(window_id) {
+            return None;
+        }
+        let mut process_id = 0u32;
+        unsafe { GetWindowThreadProcessId(HWND(window_id), Some(&mut process_id)) };
+        get_window_path_name(process_id).ok()
+    }
+
+    fn navigate_to_window(&self, window: &ApplicationWindow, policy: RestorePolicy) {
+        navigate_to_window(window, policy);
+    }
+
+    fn move_windows(&self, windows: &[ApplicationWindow]) {
+        move_windows(windows);
+    }
+
+    fn focus_window(&self, window_id: isize) {
+        focus_window(window_id);
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        enumerate_monitors()
+    }
+
+    fn enumerate_windows(&self) -> Vec<ApplicationWindow> {
+        enumerate_windows()
+    }
+}
+
+/**
+   This function will navigate to the window passed in as a parameter.
+   It will set the foreground window and set the keyboard focus to the window.
+
+    # Arguments
+    * `window: &ApplicationWindow` - A reference to the window to navigate to.
+    * `policy: RestorePolicy` - How much of the saved position, size and state to restore.
+*/
+fn navigate_to_window(window: &ApplicationWindow, policy: RestorePolicy) {
+    // Convert the isize window_id to a HWND
+    let window_handle = HWND(window.window_id);
+
+    /*
+     * we need to attach to the foreground thread to be able to set
+     * the foreground window and set keyboard focus to the window
+     */
+    let foreground_thread_handle = match attach_to_foreground_thread() {
+        Ok(handle) => handle,
+        Err(e) => {
+            println!(
+                "Failed to attach to foreground thread while navigating to a window: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    // move the window to the saved position
+    if policy == RestorePolicy::Geometry {
+        let defer_window_position = match unsafe { BeginDeferWindowPos(1) } {
+            Ok(window_pos_defer) => window_pos_defer,
+            Err(e) => {
+                println!("Failed to begin deferring window position: {}", e);
+                return;
+            }
+        };
+
+        match unsafe {
+            DeferWindowPos(
+                defer_window_position,
+                window_handle,
+                HWND_TOP,
+                window.position.x as i32,
+                window.position.y as i32,
+                window.position.width as i32,
+                window.position.height as i32,
+                SWP_SHOWWINDOW | SWP_DRAWFRAME,
+            )
+        } {
+            Ok(_) => {}
+            Err(e) => {
+                println!("Failed to defer window position: {}", e);
+                return;
+            }
+        };
+
+        // apply the window position
+        unsafe { EndDeferWindowPos(defer_window_position) };
+    }
+
+    // bring the window to the foreground
+    unsafe { SetForegroundWindow(window_handle) };
+
+    // bring the window to the top and make it active
+    unsafe { BringWindowToTop(window_handle) };
+    unsafe { SetActiveWindow(window_handle) };
+
+    // when only focusing, a minimized window is restored to how it was before minimizing
+    if policy == RestorePolicy::FocusOnly {
+        if unsafe { IsIconic(window_handle) }.as_bool() {
+            unsafe { ShowWindow(window_handle, SW_RESTORE) };
+        }
+        detach_from_foreground_thread(foreground_thread_handle);
+        return;
+    }
+
+    restore_window_state(window_handle, &window.state);
+
+    // detach from the foreground thread
+    detach_from_foreground_thread(foreground_thread_handle);
+}
+
+/// Sets the window state if it differs from the current one.
+fn restore_window_state(window_handle: HWND, state: &WindowState) {
+    let target_window_state = match state {
+        WindowState::Normal => SW_NORMAL,
+        WindowState::Maximized => SW_MAXIMIZE,
+        _ => SW_MAXIMIZE, // if a window is minimized, we want to maximize it
+    };
+
+    let mut current_window_state = WINDOWPLACEMENT::default();
+    let got_current_window_state =
+        unsafe { GetWindowPlacement(window_handle, &mut current_window_state).as_bool() };
+
+    let should_restore_window_state =
+        !got_current_window_state || current_window_state.showCmd != target_window_state;
+
+    // restore the window state if necessary
+    if should_restore_window_state {
+        unsafe { ShowWindow(window_handle, target_window_state) };
+    }
+}
+
+/// Moves and resizes all windows in a single deferred-position batch, so they are redrawn
+/// once instead of one after another.
+fn move_windows(windows: &[ApplicationWindow]) {
+    // a maximized window ignores position changes, so normal windows are restored first
+    for window in windows.iter().filter(|w| w.state == WindowState::Normal) {
+        restore_window_state(HWND(window.window_id), &window.state);
+    }
+
+    let mut defer_window_position = match unsafe { BeginDeferWindowPos(windows.len() as i32) } {
+        Ok(window_pos_defer) => window_pos_defer,
+        Err(e) => {
+            println!("Failed to begin deferring window positions: {}", e);
+            return;
+        }
+    };
+
+    for window in windows.iter().filter(|w| w.state == WindowState::Normal) {
+        // every call can return a new handle for the batch, which has to be used from then on
+        defer_window_position = match unsafe {
+            DeferWindowPos(
+                defer_window_position,
+                HWND(window.window_id),
+                HWND::default(),
+                window.position.x as i32,
+                window.position.y as i32,
+                window.position.width as i32,
+                window.position.height as i32,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+        } {
+            Ok(window_pos_defer) => window_pos_defer,
+            Err(e) => {
+                println!("Failed to defer window position: {}", e);
+                return;
+            }
+        };
+    }
+
+    // apply all window positions at once
+    unsafe { EndDeferWindowPos(defer_window_position) };
+
+    for window in windows.iter().filter(|w| w.state != WindowState::Normal) {
+        restore_window_state(HWND(window.window_id), &window.state);
+    }
+}
+
+/// Sets the foreground window and keyboard focus to the window with the given handle.
+fn focus_window(window_id: isize) {
+    let window_handle = HWND(window_id);
+
+    let foreground_thread_handle = match attach_to_foreground_thread() {
+        Ok(handle) => handle,
+        Err(e) => {
+            println!(
+                "Failed to attach to foreground thread while focusing a window: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    unsafe {
+        SetForegroundWindow(window_handle);
+        BringWindowToTop(window_handle);
+        SetActiveWindow(window_handle);
+    }
+
+    detach_from_foreground_thread(foreground_thread_handle);
+}
+
+/// Returns an ApplicationWindow for the currently active window.
+fn get_current_window() -> Option<ApplicationWindow> {
+    let window = match get_active_window() {
+        Ok(window) => window,
+        Err(_) => {
+            return None;
+        }
+    };
+
+    // get the number from the hwnd(<number>) string
+    let hwnd = window.window_id[5..window.window_id.len() - 1]
+        .parse::<isize>()
+        .unwrap();
+
+    let mut window_placement = WINDOWPLACEMENT::default();
+    unsafe {
+        if !GetWindowPlacement(HWND(hwnd), &mut window_placement).as_bool() {
+            return None;
+        }
+    }
+
+    let window_state = match window_placement.showCmd {
+        SW_SHOWMAXIMIZED => WindowState::Maximized,
+        SW_SHOWMINIMIZED => WindowState::Minimized,
+        _ => WindowState::Normal,
+    };
+
+    let process_path = match get_window_path_name(window.process_id as u32) {
+        Ok(path) => path,
+        Err(_) => {
+            return None;
+        }
+    };
+
+    let application_window = ApplicationWindow {
+        window_id: hwnd,
+        title: window.title,
+        process_path,
+        process_name: window.process_name,
+
+        position: WindowPosition {
+            x: window.position.x,
+            y: window.position.y,
+            width: window.position.width,
+            height: window.position.height,
+        },
+        state: window_state,
+        status: WindowStatus::Alive,
+        restore_policy: None,
+        placement: None,
+        group_members: vec![],
+        label: None,
+        hotkey: None,
+    };
+
+    Some(application_window)
+}
+
+/// Returns an ApplicationWindow for every visible top-level window that has a title.
+fn enumerate_windows() -> Vec<ApplicationWindow> {
+    let mut handles: Vec<isize> = Vec::new();
+
+    unsafe {
+        EnumWindows(
+            Some(enumerate_windows_proc),
+            LPARAM(&mut handles as *mut Vec<isize> as isize),
+        );
+    }
+
+    handles
+        .into_iter()
+        .filter_map(get_application_window)
+        .collect()
+}
+
+fn enumerate_monitors() -> Vec<Monitor> {
+    let mut monitors: Vec<Monitor> = Vec::new();
+
+    unsafe {
+        EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(enumerate_monitors_proc),
+            LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+        );
+    }
+
+    monitors
+}
+
+unsafe extern "system" fn enumerate_monitors_proc(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(lparam.0 as *mut Vec<Monitor>);
+
+    let mut monitor_info = MONITORINFOEXW::default();
+    monitor_info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    if !GetMonitorInfoW(monitor, &mut monitor_info as *mut _ as *mut MONITORINFO).as_bool() {
+        return BOOL(1);
+    }
+
+    // the effective DPI includes the scaling set in the display settings
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    let scale = match GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) {
+        Ok(_) => dpi_x as f64 / 96.0,
+        Err(_) => 1.0,
+    };
+
+    let device_name_length = monitor_info
+        .szDevice
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(monitor_info.szDevice.len());
+    let work_area = monitor_info.monitorInfo.rcWork;
+
+    monitors.push(Monitor {
+        id: String::from_utf16_lossy(&monitor_info.szDevice[..device_name_length]),
+        work_area: WindowPosition {
+            x: work_area.left as f64,
+            y: work_area.top as f64,
+            width: (work_area.right - work_area.left) as f64,
+            height: (work_area.bottom - work_area.top) as f64,
+        },
+        scale,
+        primary: monitor_info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+    });
+
+    BOOL(1)
+}
+
+unsafe extern "system" fn enumerate_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let handles = &mut *(lparam.0 as *mut Vec<isize>);
+
+    if IsWindowVisible(hwnd).as_bool() {
+        handles.push(hwnd.0);
+    }
+
+    // continue enumerating
+    BOOL::from(true)
+}
+
+/// Returns an ApplicationWindow for the window with the given handle.
+///
+/// Returns None if the window has no title or its process can not be queried.
+fn get_application_window(hwnd: isize) -> Option<ApplicationWindow> {
+    let title = get_window_title(hwnd)?;
+
+    let mut window_placement = WINDOWPLACEMENT::default();
+    let mut rect = RECT::default();
+    let mut process_id = 0u32;
+    unsafe {
+        if !GetWindowPlacement(HWND(hwnd), &mut window_placement).as_bool() {
+            return None;
+        }
+        if !GetWindowRect(HWND(hwnd), &mut rect).as_bool() {
+            return None;
+        }
+        GetWindowThreadProcessId(HWND(hwnd), Some(&mut process_id));
+    }
+
+    let window_state = match window_placement.showCmd {
+        SW_SHOWMAXIMIZED => WindowState::Maximized,
+        SW_SHOWMINIMIZED => WindowState::Minimized,
+        _ => WindowState::Normal,
+    };
+
+    let process_path = get_window_path_name(process_id).ok()?;
+    let process_name = Path::new(&process_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("")
+        .to_string();
+
+    Some(ApplicationWindow {
+        window_id: hwnd,
+        title,
+        process_path,
+        process_name,
+        position: WindowPosition {
+            x: rect.left as f64,
+            y: rect.top as f64,
+            width: (rect.right - rect.left) as f64,
+            height: (rect.bottom - rect.top) as f64,
+        },
+        state: window_state,
+        status: WindowStatus::Alive,
+        restore_policy: None,
+        placement: None,
+        group_members: vec![],
+        label: None,
+        hotkey: None,
+    })
+}
+
+fn get_window_title(hwnd: isize) -> Option<String> {
+    let mut title = [0u16; 1024];
+    let len = unsafe { GetWindowTextW(HWND(hwnd), &mut title) };
+
+    if len == 0 {
+        return None;
+    }
+
+    let title = String::from_utf16_lossy(&title[..len as usize]);
+
+    Some(title)
+}
+
+/// Returns the path of the executable of the process with the given process id.
+fn get_window_path_name(process_id: u32) -> Result<String, ()> {
+    let process_handle = match unsafe {
+        OpenProcess(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            BOOL::from(false),
+            process_id,
+        )
+    } {
+        Ok(handle) => handle,
+        Err(_) => return Err(()),
+    };
+
+    let mut buffer_size = MAX_PATH as u32;
+    let mut buffer: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
+    let process_path_pwstr = PWSTR::from_raw(buffer.as_mut_ptr());
+
+    let process_path = unsafe {
+        let success = QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_WIN32,
+            process_path_pwstr,
+            &mut buffer_size,
+        )
+        .as_bool();
+        CloseHandle(process_handle);
+        if !success {
+            return Err(());
+        }
+
+        process_path_pwstr.to_string().map_err(|_| ())?
+    };
+
+    let path = Path::new(&process_path)
+        .to_path_buf()
+        .to_str()
+        .ok_or(())
+        .map(|s| s.to_string())?;
+
+    Ok(path)
+}
+
+fn attach_to_foreground_thread() -> Result<u32, &'static str> {
+    unsafe {
+        let foreground_window = GetForegroundWindow();
+        if foreground_window.0 == 0 {
+            return Err("Failed to get foreground window");
+        }
+
+        // get the current foreground thread
+        let foreground_thread = GetWindowThreadProcessId(foreground_window, None);
+
+        if foreground_thread == 0 {
+            return Err("Failed to get foreground thread");
+        }
+
+        // get the current thread
+        let current_thread = GetCurrentThreadId();
+
+        // attach the current thread to the foreground thread
+        let thread_attached = current_thread == foreground_thread
+            || AttachThreadInput(current_thread, foreground_thread, true).as_bool();
+
+        if !thread_attached {
+            return Err("Failed to attach thread");
+        }
+
+        return Ok(foreground_thread);
+    }
+}
+
+fn detach_from_foreground_thread(foreground_thread: u32) {
+    unsafe {
+        let current_thread = GetCurrentThreadId();
+
+        if current_thread != foreground_thread {
+            let thread_detached =
+                AttachThreadInput(current_thread, foreground_thread, false).as_bool();
+
+            if !thread_detached {
+                println!("Failed to detach thread");
+            }
+        }
+    }
+}
+
+fn create_window() -> isize {
+    let (sender, receiver) = channel::<isize>();
+    thread::spawn(move || {
+        let h_instance = match unsafe { GetModuleHandleW(None) } {
+            Ok(h_instance) => h_instance,
+            Err(_) => panic!("Failed to get module handle"),
+        };
+
+        let mut icon_path = dbg!(match get_app_icon_filepath() {
+            Ok(icon_path) => icon_path,
+            Err(_) => panic!("Failed to get icon path"),
+        });
+        // append null terminator
+        icon_path.push('\0');
+
+        let icon = match unsafe {
+            LoadImageA(
+                h_instance,
+                PCSTR(icon_path.as_ptr()),
+                IMAGE_ICON,
+                0,
+                0,
+                LR_LOADFROMFILE,
+            )
+        } {
+            Ok(icon) => HICON(icon.0),
+            Err(e) => panic!("Failed to load icon: {}", e),
+        };
+
+        let class_name = PCSTR(b"HarpoonClass\0".as_ptr() as *const u8);
+
+        let cursor = match unsafe { LoadCursorW(None, IDC_ARROW) } {
+            Ok(cursor) => cursor,
+            Err(_) => panic!("Failed to load cursor"),
+        };
+
+        let window_class = WNDCLASSA {
+            style: WNDCLASS_STYLES(0),
+            lpfnWndProc: Some(window_proc),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hInstance: h_instance,
+            hIcon: icon,
+            hCursor: cursor,
+            hbrBackground: HBRUSH(0),
+            lpszClassName: class_name,
+            lpszMenuName: PCSTR(std::ptr::null()),
+        };
+
+        unsafe {
+            dbg!(RegisterClassA(&window_class));
+        }
+
+        let hwnd = unsafe {
+            CreateWindowExA(
+                WINDOW_EX_STYLE(0),
+                class_name,
+                PCSTR("Harpoon\0".as_ptr() as *const u8),
+                WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                None,
+                None,
+                h_instance,
+                None,
+            )
+        };
+
+        sender.send(hwnd.0).unwrap();
+
+        unsafe { ShowWindow(hwnd, SW_HIDE) };
+
+        let mut msg = MSG::default();
+        unsafe {
+            loop {
+                GetMessageA(&mut msg, None, 0, 0).as_bool();
+                TranslateMessage(&msg);
+                DispatchMessageA(&msg);
+                if msg.message == WM_NULL {
+                    break;
+                }
+            }
+        }
+    });
+
+    let hwnd = receiver.recv().unwrap();
+    hwnd
+}
+
+extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_DESTROY => {
+            unsafe { PostQuitMessage(0) };
+            LRESULT(0)
+        }
+
+        _ => unsafe { DefWindowProcA(hwnd, msg, wparam, lparam) },
+    }
+}
@@ -0,0 +1,785 @@
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_long, c_uchar, c_ulong},
+    path::Path,
+    ptr,
+    sync::Once,
+};
+
+use ::x11::{xlib, xrandr};
+use anyhow::Result;
+
+use crate::window::{
+    is_closed_id, ApplicationWindow, Monitor, RestorePolicy, WindowBackend, WindowPosition,
+    WindowState, WindowStatus,
+};
+
+/// Window backend for X11 window managers that follow the EWMH spec.
+pub struct X11Backend {
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    atoms: Atoms,
+}
+
+/// The atoms used by the backend, interned once when the backend is created.
+struct Atoms {
+    net_active_window: xlib::Atom,
+    net_client_list: xlib::Atom,
+    net_client_list_stacking: xlib::Atom,
+    net_wm_name: xlib::Atom,
+    net_wm_pid: xlib::Atom,
+    net_wm_state: xlib::Atom,
+    net_wm_state_maximized_vert: xlib::Atom,
+    net_wm_state_maximized_horz: xlib::Atom,
+    net_wm_state_hidden: xlib::Atom,
+    net_frame_extents: xlib::Atom,
+    net_workarea: xlib::Atom,
+    net_current_desktop: xlib::Atom,
+}
+
+/// Action values for `_NET_WM_STATE` client messages.
+const NET_WM_STATE_REMOVE: c_long = 0;
+const NET_WM_STATE_ADD: c_long = 1;
+
+/// Source indication for client messages, 2 means the request comes from a pager.
+const SOURCE_INDICATION_PAGER: c_long = 2;
+
+static XLIB_INIT: Once = Once::new();
+
+impl X11Backend {
+    /// Connects to the display named by the `DISPLAY` environment variable.
+    pub fn new() -> Result<Self> {
+        XLIB_INIT.call_once(|| unsafe {
+            // Xlib is also used by fltk and mki, so it has to be made thread safe before
+            // any connection is opened.
+            xlib::XInitThreads();
+            // The default error handler exits the process, errors for windows that were
+            // closed in the meantime are expected and handled by checking return values.
+            xlib::XSetErrorHandler(Some(ignore_x_error));
+        });
+
+        unsafe {
+            let display = xlib::XOpenDisplay(ptr::null());
+            if display.is_null() {
+                return Err(anyhow!("Failed to open X display, is DISPLAY set?"));
+            }
+
+            let root = xlib::XDefaultRootWindow(display);
+            let atoms = Atoms {
+                net_active_window: intern_atom(display, "_NET_ACTIVE_WINDOW"),
+                net_client_list: intern_atom(display, "_NET_CLIENT_LIST"),
+                net_client_list_stacking: intern_atom(display, "_NET_CLIENT_LIST_STACKING"),
+                net_wm_name: intern_atom(display, "_NET_WM_NAME"),
+                net_wm_pid: intern_atom(display, "_NET_WM_PID"),
+                net_wm_state: intern_atom(display, "_NET_WM_STATE"),
+                net_wm_state_maximized_vert: intern_atom(display, "_NET_WM_STATE_MAXIMIZED_VERT"),
+                net_wm_state_maximized_horz: intern_atom(display, "_NET_WM_STATE_MAXIMIZED_HORZ"),
+                net_wm_state_hidden: intern_atom(display, "_NET_WM_STATE_HIDDEN"),
+                net_frame_extents: intern_atom(display, "_NET_FRAME_EXTENTS"),
+                net_workarea: intern_atom(display, "_NET_WORKAREA"),
+                net_current_desktop: intern_atom(display, "_NET_CURRENT_DESKTOP"),
+            };
+
+            Ok(Self {
+                display,
+                root,
+                atoms,
+            })
+        }
+    }
+
+    /// Returns an ApplicationWindow for the window with the given id.
+    ///
+    /// Returns None if the window no longer exists, has no title or doesn't say which
+    /// process owns it, as the entry couldn't be matched or relaunched later.
+    fn application_window(&self, window: xlib::Window) -> Option<ApplicationWindow> {
+        let title = self.title(window)?;
+        let position = self.position(window)?;
+
+        let process_path = self.process_path(window)?;
+
+        let process_name = Path::new(&process_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        Some(ApplicationWindow {
+            window_id: window as isize,
+            title,
+            process_path,
+            position,
+            state: self.state(window),
+            process_name,
+            status: WindowStatus::Alive,
+            restore_policy: None,
+            placement: None,
+            group_members: vec![],
+            label: None,
+            hotkey: None,
+        })
+    }
+
+    /// Reads `_NET_WM_NAME`, falling back to the ICCCM `WM_NAME`.
+    fn title(&self, window: xlib::Window) -> Option<String> {
+        if let Some(bytes) = self.property_bytes(window, self.atoms.net_wm_name) {
+            if !bytes.is_empty() {
+                return Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+        }
+
+        unsafe {
+            let mut name: *mut c_char = ptr::null_mut();
+            if xlib::XFetchName(self.display, window, &mut name) == 0 || name.is_null() {
+                return None;
+            }
+            let title = CStr::from_ptr(name).to_string_lossy().into_owned();
+            xlib::XFree(name as *mut _);
+            Some(title)
+        }
+    }
+
+    /// Returns the position of the window frame and the size of the window itself.
+    ///
+    /// This is what `XMoveResizeWindow` expects for windows with the default gravity.
+    fn position(&self, window: xlib::Window) -> Option<WindowPosition> {
+        unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if xlib::XGetWindowAttributes(self.display, window, &mut attributes) == 0 {
+                return None;
+            }
+
+            let (mut x, mut y) = (0, 0);
+            let mut child: xlib::Window = 0;
+            xlib::XTranslateCoordinates(
+                self.display,
+                window,
+                self.root,
+                0,
+                0,
+                &mut x,
+                &mut y,
+                &mut child,
+            );
+
+            let extents = self
+                .long_properties(window, self.atoms.net_frame_extents)
+                .unwrap_or_default();
+            let left = extents.first().copied().unwrap_or(0);
+            let top = extents.get(2).copied().unwrap_or(0);
+
+            Some(WindowPosition {
+                x: (x as c_long - left) as f64,
+                y: (y as c_long - top) as f64,
+                width: attributes.width as f64,
+                height: attributes.height as f64,
+            })
+        }
+    }
+
+    fn state(&self, window: xlib::Window) -> WindowState {
+        let states = self
+            .long_properties(window, self.atoms.net_wm_state)
+            .unwrap_or_default();
+        let has_state = |atom: xlib::Atom| states.iter().any(|s| *s as xlib::Atom == atom);
+
+        if has_state(self.atoms.net_wm_state_hidden) {
+            WindowState::Minimized
+        } else if has_state(self.atoms.net_wm_state_maximized_vert)
+            && has_state(self.atoms.net_wm_state_maximized_horz)
+        {
+            WindowState::Maximized
+        } else {
+            WindowState::Normal
+        }
+    }
+
+    fn exists(&self, window: xlib::Window) -> bool {
+        unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            xlib::XGetWindowAttributes(self.display, window, &mut attributes) != 0
+        }
+    }
+
+    /// Returns the top-level windows managed by the window manager, the topmost first.
+    ///
+    /// Without a window manager there is no `_NET_CLIENT_LIST`, in that case the
+    /// mapped children of the root window are used instead.
+    fn client_windows(&self) -> Vec<xlib::Window> {
+        // the stacking order lists the bottom window first
+        if let Some(clients) = self.long_properties(self.root, self.atoms.net_client_list_stacking)
+        {
+            return clients
+                .into_iter()
+                .rev()
+                .map(|c| c as xlib::Window)
+                .collect();
+        }
+        if let Some(clients) = self.long_properties(self.root, self.atoms.net_client_list) {
+            return clients.into_iter().map(|c| c as xlib::Window).collect();
+        }
+
+        unsafe {
+            let (mut root, mut parent) = (0, 0);
+            let mut children: *mut xlib::Window = ptr::null_mut();
+            let mut count = 0;
+            if xlib::XQueryTree(
+                self.display,
+                self.root,
+                &mut root,
+                &mut parent,
+                &mut children,
+                &mut count,
+            ) == 0
+            {
+                return vec![];
+            }
+            if children.is_null() {
+                return vec![];
+            }
+
+            // children are listed from the bottom of the stacking order up
+            let windows = std::slice::from_raw_parts(children, count as usize)
+                .iter()
+                .rev()
+                .copied()
+                .filter(|window| {
+                    let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+                    xlib::XGetWindowAttributes(self.display, *window, &mut attributes) != 0
+                        && attributes.map_state == xlib::IsViewable
+                })
+                .collect();
+            xlib::XFree(children as *mut _);
+            windows
+        }
+    }
+
+    /// Reads a property as raw bytes, used for 8 bit formats such as strings.
+    fn property_bytes(&self, window: xlib::Window, property: xlib::Atom) -> Option<Vec<u8>> {
+        let (format, items, data) = self.raw_property(window, property)?;
+        if format != 8 {
+            unsafe { xlib::XFree(data as *mut _) };
+            return None;
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(data, items as usize).to_vec() };
+        unsafe { xlib::XFree(data as *mut _) };
+        Some(bytes)
+    }
+
+    /// Returns the area of the current desktop that isn't covered by panels and docks,
+    /// spanning all monitors.
+    fn work_area(&self) -> Option<WindowPosition> {
+        let desktop = self
+            .cardinal_property(self.root, self.atoms.net_current_desktop)
+            .unwrap_or(0) as usize;
+        let work_areas = self.long_properties(self.root, self.atoms.net_workarea)?;
+        let work_area = work_areas.get(desktop * 4..desktop * 4 + 4)?;
+        Some(WindowPosition {
+            x: work_area[0] as f64,
+            y: work_area[1] as f64,
+            width: work_area[2] as f64,
+            height: work_area[3] as f64,
+        })
+    }
+
+    /// Returns the bounds of the screen, for servers without RandR monitors.
+    fn screen_bounds(&self) -> Option<WindowPosition> {
+        unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if xlib::XGetWindowAttributes(self.display, self.root, &mut attributes) == 0 {
+                return None;
+            }
+            Some(WindowPosition {
+                x: 0.0,
+                y: 0.0,
+                width: attributes.width as f64,
+                height: attributes.height as f64,
+            })
+        }
+    }
+
+    /// Returns the path of the program that owns the window, from its `_NET_WM_PID`.
+    fn process_path(&self, window: xlib::Window) -> Option<String> {
+        let pid = self.cardinal_property(window, self.atoms.net_wm_pid)?;
+        let path = std::fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+        path.to_str().map(|path| path.to_string())
+    }
+
+    fn atom_name(&self, atom: xlib::Atom) -> String {
+        unsafe {
+            let name = xlib::XGetAtomName(self.display, atom);
+            if name.is_null() {
+                return String::new();
+            }
+            let atom_name = CStr::from_ptr(name).to_string_lossy().into_owned();
+            xlib::XFree(name as *mut _);
+            atom_name
+        }
+    }
+
+    /// Reads a property with a 32 bit format, such as windows, atoms and cardinals.
+    ///
+    /// Xlib returns 32 bit items as longs, regardless of the size of a long.
+    fn long_properties(&self, window: xlib::Window, property: xlib::Atom) -> Option<Vec<c_long>> {
+        let (format, items, data) = self.raw_property(window, property)?;
+        if format != 32 {
+            unsafe { xlib::XFree(data as *mut _) };
+            return None;
+        }
+        let longs =
+            unsafe { std::slice::from_raw_parts(data as *const c_long, items as usize).to_vec() };
+        unsafe { xlib::XFree(data as *mut _) };
+        Some(longs)
+    }
+
+    fn cardinal_property(&self, window: xlib::Window, property: xlib::Atom) -> Option<c_ulong> {
+        self.long_properties(window, property)?
+            .first()
+            .map(|value| *value as c_ulong)
+    }
+
+    /// Returns the format, item count and data of a property.
+    ///
+    /// The data has to be freed with `XFree`.
+    fn raw_property(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+    ) -> Option<(c_int, c_ulong, *mut c_uchar)> {
+        unsafe {
+            let mut actual_type: xlib::Atom = 0;
+            let mut format: c_int = 0;
+            let mut items: c_ulong = 0;
+            let mut bytes_after: c_ulong = 0;
+            let mut data: *mut c_uchar = ptr::null_mut();
+
+            let status = xlib::XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                0,
+                // the length is in 32 bit multiples, this is plenty for titles and client lists
+                4096,
+                xlib::False,
+                xlib::AnyPropertyType as xlib::Atom,
+                &mut actual_type,
+                &mut format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+            if actual_type == 0 {
+                xlib::XFree(data as *mut _);
+                return None;
+            }
+
+            Some((format, items, data))
+        }
+    }
+
+    /// Sends an EWMH client message about `window` to the root window.
+    fn send_client_message(
+        &self,
+        window: xlib::Window,
+        message_type: xlib::Atom,
+        data: [c_long; 5],
+    ) {
+        unsafe {
+            let mut message_data = xlib::ClientMessageData::new();
+            for (index, value) in data.iter().enumerate() {
+                message_data.set_long(index, *value);
+            }
+
+            let mut event = xlib::XEvent::from(xlib::XClientMessageEvent {
+                type_: xlib::ClientMessage,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.display,
+                window,
+                message_type,
+                format: 32,
+                data: message_data,
+            });
+
+            xlib::XSendEvent(
+                self.display,
+                self.root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+        }
+    }
+
+    fn set_maximized(&self, window: xlib::Window, maximized: bool) {
+        let action = match maximized {
+            true => NET_WM_STATE_ADD,
+            false => NET_WM_STATE_REMOVE,
+        };
+        self.send_client_message(
+            window,
+            self.atoms.net_wm_state,
+            [
+                action,
+                self.atoms.net_wm_state_maximized_vert as c_long,
+                self.atoms.net_wm_state_maximized_horz as c_long,
+                SOURCE_INDICATION_PAGER,
+                0,
+            ],
+        );
+    }
+
+    fn activate(&self, window: xlib::Window) {
+        self.send_client_message(
+            window,
+            self.atoms.net_active_window,
+            [
+                SOURCE_INDICATION_PAGER,
+                xlib::CurrentTime as c_long,
+                0,
+                0,
+                0,
+            ],
+        );
+        unsafe {
+            xlib::XRaiseWindow(self.display, window);
+        }
+    }
+
+    /// Restores as much of the saved position, size and state of a window as the policy asks
+    /// for, the requests are not flushed.
+    fn restore_layout(&self, window: &ApplicationWindow, policy: RestorePolicy) {
+        let window_handle = window.window_id as xlib::Window;
+        let current_state = self.state(window_handle);
+
+        match window.state {
+            _ if policy == RestorePolicy::FocusOnly => {}
+            WindowState::Normal => {
+                // a maximized window ignores move requests, so it has to be restored first
+                if current_state == WindowState::Maximized {
+                    self.set_maximized(window_handle, false);
+                }
+
+                if policy == RestorePolicy::Geometry {
+                    unsafe {
+                        xlib::XMoveResizeWindow(
+                            self.display,
+                            window_handle,
+                            window.position.x as c_int,
+                            window.position.y as c_int,
+                            window.position.width.max(1.0) as u32,
+                            window.position.height.max(1.0) as u32,
+                        );
+                    }
+                }
+            }
+            // if a window is minimized, we want to maximize it
+            _ => {
+                if current_state != WindowState::Maximized {
+                    self.set_maximized(window_handle, true);
+                }
+            }
+        }
+    }
+}
+
+impl WindowBackend for X11Backend {
+    fn current_window(&self) -> Option<ApplicationWindow> {
+        let active = self.cardinal_property(self.root, self.atoms.net_active_window)?;
+        if active == 0 {
+            return None;
+        }
+        self.application_window(active as xlib::Window)
+    }
+
+    fn is_alive(&self, window_id: isize) -> bool {
+        !is_closed_id(window_id) && self.exists(window_id as xlib::Window)
+    }
+
+    /// X11 has no cheap way to tell whether a client is hung, `_NET_WM_PING` needs an event
+    /// loop, so windows are always considered responsive.
+    fn is_responsive(&self, _window_id: isize) -> bool {
+        true
+    }
+
+    fn window_title(&self, window_id: isize) -> Option<String> {
+        self.title(window_id as xlib::Window)
+    }
+
+    fn window_process_path(&self, window_id: isize) -> Option<String> {
+        if !self.is_alive(window_id) {
+            return None;
+        }
+        self.process_path(window_id as xlib::Window)
+    }
+
+    fn navigate_to_window(&self, window: &ApplicationWindow, policy: RestorePolicy) {
+        let window_handle = window.window_id as xlib::Window;
+        self.restore_layout(window, policy);
+
+        // activating a window also restores it when it is minimized
+        self.activate(window_handle);
+
+        unsafe {
+            xlib::XFlush(self.display);
+        }
+    }
+
+    fn move_windows(&self, windows: &[ApplicationWindow]) {
+        for window in windows {
+            self.restore_layout(window, RestorePolicy::Geometry);
+        }
+
+        // the requests are buffered until here, so they reach the window manager together
+        unsafe {
+            xlib::XFlush(self.display);
+        }
+    }
+
+    fn focus_window(&self, window_id: isize) {
+        self.activate(window_id as xlib::Window);
+        unsafe {
+            xlib::XFlush(self.display);
+        }
+    }
+
+    /// X11 has no per-monitor DPI, so the scale of every monitor is 1.0.
+    fn monitors(&self) -> Vec<Monitor> {
+        let work_area = self.work_area();
+        let bounds_to_monitor = |id: String, bounds: WindowPosition, primary: bool| Monitor {
+            id,
+            work_area: work_area
+                .as_ref()
+                .and_then(|work_area| intersect(&bounds, work_area))
+                .unwrap_or(bounds),
+            scale: 1.0,
+            primary,
+        };
+
+        let mut count = 0;
+        let infos =
+            unsafe { xrandr::XRRGetMonitors(self.display, self.root, xlib::True, &mut count) };
+        if infos.is_null() || count <= 0 {
+            if !infos.is_null() {
+                unsafe { xrandr::XRRFreeMonitors(infos) };
+            }
+            return self
+                .screen_bounds()
+                .map(|bounds| bounds_to_monitor("screen".to_string(), bounds, true))
+                .into_iter()
+                .collect();
+        }
+
+        let monitors = unsafe { std::slice::from_raw_parts(infos, count as usize) }
+            .iter()
+            .map(|info| {
+                let bounds = WindowPosition {
+                    x: info.x as f64,
+                    y: info.y as f64,
+                    width: info.width as f64,
+                    height: info.height as f64,
+                };
+                bounds_to_monitor(self.atom_name(info.name), bounds, info.primary != 0)
+            })
+            .collect();
+        unsafe { xrandr::XRRFreeMonitors(infos) };
+        monitors
+    }
+
+    fn enumerate_windows(&self) -> Vec<ApplicationWindow> {
+        self.client_windows()
+            .into_iter()
+            .filter_map(|window| self.application_window(window))
+            .collect()
+    }
+}
+
+impl Drop for X11Backend {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+/// Returns the overlap of two rectangles, if they overlap.
+fn intersect(a: &WindowPosition, b: &WindowPosition) -> Option<WindowPosition> {
+    let x = a.x.max(b.x);
+    let y = a.y.max(b.y);
+    let width = (a.x + a.width).min(b.x + b.width) - x;
+    let height = (a.y + a.height).min(b.y + b.height) - y;
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+    Some(WindowPosition {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+unsafe fn intern_atom(display: *mut xlib::Display, name: &str) -> xlib::Atom {
+    let name = CString::new(name).unwrap();
+    xlib::XInternAtom(display, name.as_ptr(), xlib::False)
+}
+
+unsafe extern "C" fn ignore_x_error(
+    _display: *mut xlib::Display,
+    _event: *mut xlib::XErrorEvent,
+) -> c_int {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    //! These tests need an X server, so they are ignored by default. Run them with
+    //! `xvfb-run cargo test -- --ignored`.
+
+    use super::*;
+
+    fn connect() -> X11Backend {
+        X11Backend::new().expect("these tests need an X server")
+    }
+
+    /// A window created by a test, destroyed when the test ends even if it fails.
+    struct TestWindow<'a> {
+        backend: &'a X11Backend,
+        window: xlib::Window,
+    }
+
+    impl Drop for TestWindow<'_> {
+        fn drop(&mut self) {
+            unsafe {
+                xlib::XDestroyWindow(self.backend.display, self.window);
+                xlib::XSync(self.backend.display, xlib::False);
+            }
+        }
+    }
+
+    /// Creates and maps a titled window owned by this process.
+    fn create_test_window<'a>(backend: &'a X11Backend, title: &str) -> TestWindow<'a> {
+        unsafe {
+            let window =
+                xlib::XCreateSimpleWindow(backend.display, backend.root, 10, 20, 300, 200, 0, 0, 0);
+
+            xlib::XChangeProperty(
+                backend.display,
+                window,
+                backend.atoms.net_wm_name,
+                intern_atom(backend.display, "UTF8_STRING"),
+                8,
+                xlib::PropModeReplace,
+                title.as_ptr(),
+                title.len() as c_int,
+            );
+
+            let pid = std::process::id() as c_ulong;
+            xlib::XChangeProperty(
+                backend.display,
+                window,
+                backend.atoms.net_wm_pid,
+                xlib::XA_CARDINAL,
+                32,
+                xlib::PropModeReplace,
+                &pid as *const c_ulong as *const c_uchar,
+                1,
+            );
+
+            xlib::XMapWindow(backend.display, window);
+            xlib::XSync(backend.display, xlib::False);
+            TestWindow { backend, window }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn reads_window_title_and_process() {
+        let backend = connect();
+        let test_window = create_test_window(&backend, "harpoon test window");
+        let window = test_window.window;
+
+        assert_eq!(
+            backend.window_title(window as isize).as_deref(),
+            Some("harpoon test window")
+        );
+
+        let application_window = backend.application_window(window).unwrap();
+        let current_exe = std::env::current_exe().unwrap();
+        assert_eq!(
+            application_window.process_path,
+            current_exe.to_str().unwrap()
+        );
+        assert!(backend
+            .enumerate_windows()
+            .iter()
+            .any(|w| w.window_id == window as isize));
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn closed_window_is_not_alive() {
+        let backend = connect();
+        let test_window = create_test_window(&backend, "harpoon closed window");
+        let window = test_window.window;
+        assert!(backend.is_alive(window as isize));
+
+        drop(test_window);
+        assert!(!backend.is_alive(window as isize));
+        assert_eq!(backend.window_title(window as isize), None);
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn navigate_restores_geometry() {
+        let backend = connect();
+        let test_window = create_test_window(&backend, "harpoon moved window");
+        let window = test_window.window;
+
+        let mut application_window = backend.application_window(window).unwrap();
+        application_window.position = WindowPosition {
+            x: 40.0,
+            y: 50.0,
+            width: 320.0,
+            height: 240.0,
+        };
+        backend.navigate_to_window(&application_window, RestorePolicy::Geometry);
+        unsafe { xlib::XSync(backend.display, xlib::False) };
+
+        let position = backend.position(window).unwrap();
+        assert_eq!(position.width, 320.0);
+        assert_eq!(position.height, 240.0);
+
+        // the frame extents are subtracted from the position, but a window manager may not
+        // have published them yet when it reparents the window
+        let extents = backend
+            .long_properties(window, backend.atoms.net_frame_extents)
+            .unwrap_or_default();
+        let left = extents.first().copied().unwrap_or(0) as f64;
+        let top = extents.get(2).copied().unwrap_or(0) as f64;
+        assert!((position.x - 40.0).abs() <= left, "x is {}", position.x);
+        assert!((position.y - 50.0).abs() <= top, "y is {}", position.y);
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn windows_without_a_process_are_skipped() {
+        let backend = connect();
+        let test_window = create_test_window(&backend, "harpoon window without a pid");
+        let window = test_window.window;
+        unsafe {
+            xlib::XDeleteProperty(backend.display, window, backend.atoms.net_wm_pid);
+            xlib::XSync(backend.display, xlib::False);
+        }
+
+        assert!(backend.application_window(window).is_none());
+        assert!(!backend
+            .enumerate_windows()
+            .iter()
+            .any(|w| w.window_id == window as isize));
+    }
+}